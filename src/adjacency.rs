@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorableboard::IndexedBoardState;
+use crate::colorpoint::color_name;
+
+// Which pieces of a board touch, and along how many edges. Nodes are
+// indexes into the board's polyominoes (not ids, which can be anything),
+// so the graph stays valid for as long as the board's pieces don't move.
+// Colors aren't part of the graph, so recoloring doesn't change it.
+//
+// Pieces that only touch at corners are kept separately, so the same
+// graph can answer for any kind of adjacency
+
+/// When two pieces count as neighbors (and so need different colors)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Adjacency {
+    #[default]
+    Edge,               // They share an edge
+    King,               // They share an edge or touch at a corner
+    MinContact(usize)   // They share at least this many unit edges. Shorter contacts don't count at all
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdjacencyGraph {
+    pub ids: Vec<usize>,                            // Piece id of each node
+    pub edges: BTreeMap<(usize, usize), usize>,     // Lower node first, to the number of unit edges shared
+    pub corners: BTreeMap<(usize, usize), usize>    // Same, for pieces that touch only at corners
+}
+
+impl AdjacencyGraph {
+    pub fn new(board: &ColorableBoard) -> AdjacencyGraph {
+        let mut edges = BTreeMap::new();
+        let mut corners = BTreeMap::new();
+
+        for y in 0..board.get_height() {
+            for x in 0..board.get_width() {
+                for (nx, ny) in [(x+1, y), (x, y+1)] {
+                    if let Some(pair) = piece_pair(board, (x, y), (nx, ny)) {
+                        *edges.entry(pair).or_insert(0) += 1;
+                    }
+                }
+
+                for (nx, ny) in [(x+1, y+1), (x-1, y+1)] {
+                    if let Some(pair) = piece_pair(board, (x, y), (nx, ny)) {
+                        *corners.entry(pair).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        corners.retain(|pair, _count| !edges.contains_key(pair));
+
+        AdjacencyGraph { ids: board.polyominoes.iter().map(|p| p.get_id()).collect(), edges, corners }
+    }
+
+    /// Every pair of neighbors, lower node first
+    pub fn pairs(&self, adjacency: Adjacency) -> Vec<(usize, usize)> {
+        match adjacency {
+            Adjacency::Edge => self.edges.keys().cloned().collect(),
+            Adjacency::King => self.edges.keys().chain(self.corners.keys()).cloned().collect(),
+            Adjacency::MinContact(min) => self.edges.iter().filter(|(_pair, length)| **length >= min).map(|(pair, _length)| *pair).collect()
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn neighbors(&self, node: usize, adjacency: Adjacency) -> Vec<usize> {
+        self.pairs(adjacency).iter()
+            .filter_map(|(a, b)| if *a == node { Some(*b) } else if *b == node { Some(*a) } else { None })
+            .collect()
+    }
+
+    /// Number of unit edges two pieces share, zero if they don't touch
+    pub fn shared_edges(&self, a: usize, b: usize) -> usize {
+        *self.edges.get(&(a.min(b), a.max(b))).unwrap_or(&0)
+    }
+
+    /// For graphviz ("dot -Tsvg"). Longer shared edges get heavier lines
+    /// and pieces that only touch at corners get dashed ones
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph pieces {\n");
+
+        for (node, id) in self.ids.iter().enumerate() {
+            dot.push_str(&format!("  n{} [label=\"#{}\"];\n", node, id));
+        }
+
+        for ((a, b), length) in &self.edges {
+            dot.push_str(&format!("  n{} -- n{} [label=\"{}\", penwidth={}];\n", a, b, length, length));
+        }
+
+        for (a, b) in self.corners.keys() {
+            dot.push_str(&format!("  n{} -- n{} [style=dashed];\n", a, b));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// The two pieces at these spaces, if they are different pieces
+fn piece_pair(board: &ColorableBoard, (x1, y1): (i16, i16), (x2, y2): (i16, i16)) -> Option<(usize, usize)> {
+    match (board.get(x1, y1), board.get(x2, y2)) {
+        (IndexedBoardState::Full(p1, _, _, _), IndexedBoardState::Full(p2, _, _, _)) if p1 != p2 => Some((p1.min(p2), p1.max(p2))),
+        _ => None
+    }
+}
+
+/// The planar dual of a colored board for graphviz: a node for each
+/// piece, labeled with its shape and filled with its colors (split like a
+/// pie if there is more than one), and an edge wherever two pieces
+/// touch. Nodes are pinned to the middle of their piece, so "neato -n"
+/// lays the graph out like the board
+pub fn coloring_to_dot(board: &ColorableBoard) -> String {
+    let graph = board.adjacency_graph();
+    let mut dot = String::from("graph coloring {\n  node [shape=circle, fontname=\"Helvetica-Bold\"];\n");
+
+    for (node, poly) in board.polyominoes.iter().enumerate() {
+        let label = poly.pentomino_name().map_or(format!("#{}", poly.get_id()), |name| name.to_string());
+
+        let mut colors: Vec<&str> = Vec::new();
+        let mut cells = 0;
+        let (mut sum_x, mut sum_y) = (0i32, 0i32);
+
+        for y in 0..board.get_height() {
+            for x in 0..board.get_width() {
+                if let IndexedBoardState::Full(p_idx, pt_idx, _x, _y) = board.get(x, y) {
+                    if p_idx == node {
+                        let name = color_name(board.get_point(p_idx, pt_idx).get_color());
+                        if !colors.contains(&name) {
+                            colors.push(name);
+                        }
+                        cells += 1;
+                        sum_x += x as i32;
+                        sum_y += y as i32;
+                    }
+                }
+            }
+        }
+
+        let style = if colors.len() > 1 { "wedged" } else { "filled" };
+        let (cx, cy) = (sum_x as f64 / cells.max(1) as f64, sum_y as f64 / cells.max(1) as f64);
+
+        dot.push_str(&format!("  n{} [label=\"{}\", style={}, fillcolor=\"{}\", pos=\"{:.1},{:.1}\"];\n",
+                              node, label, style, colors.join(":"), cx * 72.0, -cy * 72.0));
+    }
+
+    for ((a, b), length) in &graph.edges {
+        dot.push_str(&format!("  n{} -- n{} [penwidth={}];\n", a, b, length));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adjacency::*;
+    use crate::boardcolorer::color_board_with;
+    use crate::utils::color_count;
+
+    // +-+-+-+
+    // |5  |7|
+    // +-+-+ +
+    // |6|7  |
+    // +-+-+-+
+    fn build_board() -> ColorableBoard {
+        ColorableBoard::from_cells(3, 2, &vec![Some((5, '0')), Some((5, '0')), Some((7, '1')),
+                                               Some((6, '1')), Some((7, '1')), Some((7, '1'))])
+    }
+
+    #[test]
+    fn shared_edges() {
+        let b = build_board();
+        let graph = AdjacencyGraph::new(&b);
+
+        let node = |id: usize| graph.ids.iter().position(|i| *i == id).unwrap();
+
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.shared_edges(node(5), node(7)), 2);
+        assert_eq!(graph.shared_edges(node(5), node(6)), 1);
+        assert_eq!(graph.shared_edges(node(6), node(7)), 1);
+        assert_eq!(graph.neighbors(node(6), Adjacency::Edge).len(), 2);
+        assert!(graph.corners.is_empty());
+        assert_eq!(graph.pairs(Adjacency::MinContact(2)), vec![(node(5).min(node(7)), node(5).max(node(7)))]);
+    }
+
+    // +-+-+
+    // |0|1|
+    // +-+-+
+    // |2|3|
+    // +-+-+
+    #[test]
+    fn corners() {
+        let b = ColorableBoard::from_cells(2, 2, &vec![Some((0, '0')), Some((1, '0')), Some((2, '0')), Some((3, '0'))]);
+        let graph = AdjacencyGraph::new(&b);
+
+        assert_eq!(graph.pairs(Adjacency::Edge).len(), 4);
+        assert_eq!(graph.pairs(Adjacency::King).len(), 6);
+
+        let mut colored = b.clone();
+        color_board_with(&mut colored, Adjacency::King);
+        assert_eq!(color_count(&colored).len(), 4);
+
+        color_board_with(&mut colored, Adjacency::Edge);
+        assert_eq!(color_count(&colored).len(), 2);
+    }
+
+    #[test]
+    fn dot() {
+        let dot = AdjacencyGraph::new(&build_board()).to_dot();
+
+        assert!(dot.starts_with("graph pieces {"));
+        assert_eq!(dot.matches(" -- ").count(), 3);
+        assert!(dot.contains("label=\"#7\""));
+    }
+
+    #[test]
+    fn coloring_dot() {
+        let dot = coloring_to_dot(&build_board());
+
+        assert!(dot.contains("style=filled, fillcolor=\"red\""));
+        assert!(dot.contains("style=filled, fillcolor=\"blue\""));
+        assert_eq!(dot.matches(" -- ").count(), 3);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use polyomino::point::Point;
+use polyomino::point::SimplePoint;
+use polyomino::polyomino::Polyomino;
+
+use crate::colorableboard::ColorableBoard;
+use crate::utils::find_colored_tilings;
+use crate::utils::find_single_color_polyomino_pattern;
+use crate::utils::has_all_polyomino_patterns;
+use crate::utils::Orientation;
+
+// The overlaid target is always one answer. Any other arrangement of the
+// colored pieces that also has every pattern in the colors is an
+// alternative answer, and we want to know how it differs from the
+// intended one so the coloring can be tweaked to get rid of it.
+//
+// Pieces are identified by their id and patterns by their index in the
+// list of all polyominoes
+
+#[derive(Clone, Debug)]
+pub struct AlternativeAnswer {
+    pub orientation: Orientation,       // How the alternative was turned to line it up with the intended answer
+    pub moved_pieces: Vec<usize>,       // Pieces that cover different squares
+    pub turned_pieces: Vec<usize>,      // Pieces that cover the same squares, but rotated or flipped
+    pub reformed_patterns: Vec<usize>,  // Patterns that are made from different squares
+}
+
+/// Returns a summary for every answer that is not just the intended
+/// answer rotated or flipped
+pub fn find_alternative_answers<P: Polyomino>(intended: &ColorableBoard, answers: &Vec<&ColorableBoard>, all_polyominoes: &Vec<Vec<P>>) -> Vec<AlternativeAnswer> {
+    let intended_placements = intended.piece_cells();
+    let intended_patterns = pattern_cells(intended, all_polyominoes);
+
+    let mut alternatives = Vec::new();
+
+    for answer in answers {
+        // Line the answer up with the intended answer the way that
+        // disturbs the fewest pieces
+        let (orientation, moved_pieces, turned_pieces) = Orientation::all().into_iter()
+            .map(|o| {
+                let (moved, turned) = compare_pieces(&intended_placements, &answer.reoriented(o).piece_cells());
+                (o, moved, turned)
+            })
+            .min_by_key(|(_o, moved, turned)| moved.len() + turned.len())
+            .unwrap();
+
+        if moved_pieces.is_empty() && turned_pieces.is_empty() {
+            continue;
+        }
+
+        let answer_patterns = pattern_cells(&answer.reoriented(orientation), all_polyominoes);
+
+        let reformed_patterns = (0..all_polyominoes.len())
+            .filter(|idx| intended_patterns[*idx] != answer_patterns[*idx])
+            .collect();
+
+        alternatives.push(AlternativeAnswer { orientation, moved_pieces, turned_pieces, reformed_patterns });
+    }
+
+    alternatives
+}
+
+/// Solves the board with its own colored pieces and returns the
+/// alternatives to the board as it stands
+pub fn solve_for_alternative_answers<P: Polyomino>(board: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>) -> Vec<AlternativeAnswer> {
+    let tilings = find_colored_tilings(board);
+    let answers: Vec<&ColorableBoard> = tilings.iter()
+        .filter(|s| has_all_polyomino_patterns(s, all_polyominoes))
+        .collect();
+
+    find_alternative_answers(board, &answers, all_polyominoes)
+}
+
+fn compare_pieces(intended: &HashMap<usize, Vec<(i16, i16, char)>>, other: &HashMap<usize, Vec<(i16, i16, char)>>) -> (Vec<usize>, Vec<usize>) {
+    let mut moved = Vec::new();
+    let mut turned = Vec::new();
+
+    let mut ids: Vec<&usize> = intended.keys().collect();
+    ids.sort();
+
+    for id in ids {
+        let intended_cells = &intended[id];
+
+        match other.get(id) {
+            Some(other_cells) if other_cells == intended_cells => {}
+            Some(other_cells) if other_cells.iter().map(|(x, y, _c)| (x, y)).eq(intended_cells.iter().map(|(x, y, _c)| (x, y))) => turned.push(*id),
+            _ => moved.push(*id)
+        }
+    }
+
+    (moved, turned)
+}
+
+// The squares that make up each pattern, if the pattern is there at all
+fn pattern_cells<P: Polyomino>(board: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>) -> Vec<Option<Vec<(i16, i16)>>> {
+    all_polyominoes.iter()
+        .map(|p_rotations| p_rotations.iter().find_map(|p| find_single_color_polyomino_pattern(board, p)))
+        .map(|points: Option<Vec<SimplePoint>>| points.map(|pts| {
+            let mut cells: Vec<(i16, i16)> = pts.iter().map(|pt| (pt.x(), pt.y())).collect();
+            cells.sort();
+            cells
+        }))
+        .collect()
+}
+
+impl fmt::Display for AlternativeAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_ids(f: &mut fmt::Formatter, label: &str, ids: &Vec<usize>) -> fmt::Result {
+            if ids.is_empty() {
+                writeln!(f, "  {}: none", label)
+            } else {
+                writeln!(f, "  {}: {}", label, ids.iter().map(|id| format!("#{}", id)).collect::<Vec<String>>().join(", "))
+            }
+        }
+
+        writeln!(f, "Alternative answer (compared as {:?})", self.orientation)?;
+        write_ids(f, "Moved pieces", &self.moved_pieces)?;
+        write_ids(f, "Rotated/flipped in place", &self.turned_pieces)?;
+        write_ids(f, "Shapes formed differently", &self.reformed_patterns)
+    }
+}
@@ -1,66 +1,235 @@
 use heuristic_graph_coloring::*;
-use std::cmp;
+use std::cmp::Reverse;
 use std::collections::HashSet;
-use crate::colorpolyomino::ColorPolyomino;
+use std::fmt;
+use crate::adjacency::Adjacency;
+use crate::adjacency::AdjacencyGraph;
 use crate::colorableboard::ColorableBoard;
 use crate::colorableboard::IndexedBoardState;
+use crate::rng::Rng;
 
 #[allow(dead_code)]
 
 pub fn color_board(b: &mut ColorableBoard) {
-    let colors: Vec<usize> = find_coloring(b);
+    color_board_with(b, Adjacency::Edge);
+}
 
-    for x in 0..b.width {
-        for y in 0..b.height {
-            if let IndexedBoardState::Full(poly_idx, pt_idx, _x, _y) = b.get(x, y) {
-                let poly:&mut ColorPolyomino = b.get_polyomino_mut(poly_idx);
+/// Colors the pieces so that no two neighbors are the same color, for
+/// the given meaning of neighbor. King adjacency (corners count) usually
+/// needs more colors but looks cleaner
+pub fn color_board_with(b: &mut ColorableBoard, adjacency: Adjacency) {
+    let colors: Vec<usize> = find_coloring(b, adjacency);
+
+    apply_coloring(b, &colors);
+}
+
+// The most colors a pinned coloring will use (one for each color the
+// displays know about)
+const MAX_PINNED_COLORS: usize = 6;
+
+/// How to color a base board. Pins are (pentomino letter, color) and fix
+/// the color of every piece of that shape, e.g. ('X', '0') to always
+/// make the X red
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColoringOptions {
+    pub adjacency: Adjacency,
+    pub pins: Vec<(char, char)>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PinError {
+    UnknownPiece(char),             // No piece has this letter
+    BadColor(char),                 // Not a color we can display
+    NeighborsPinned(char, char),    // Two neighbors are pinned to the same color
+    NoColoring                      // The pins can't be extended with the colors we have
+}
+
+/// Colors the board, keeping to the pins. Without pins this is the same
+/// as color_board_with. With pins it is a backtracking search for a
+/// coloring with as few colors as possible
+pub fn color_board_pinned(b: &mut ColorableBoard, options: &ColoringOptions) -> Result<(), PinError> {
+    if options.pins.is_empty() {
+        color_board_with(b, options.adjacency);
+        return Ok(());
+    }
+
+    let graph = b.adjacency_graph();
+    let mut pinned: Vec<Option<usize>> = vec![None; graph.node_count()];
+
+    for (letter, color) in &options.pins {
+        let color_idx = match color.to_digit(16) {
+            Some(idx) if (idx as usize) < MAX_PINNED_COLORS => idx as usize,
+            _ => return Err(PinError::BadColor(*color))
+        };
+
+        let nodes: Vec<usize> = b.polyominoes.iter().enumerate()
+            .filter(|(_idx, poly)| poly.pentomino_name() == Some(*letter))
+            .map(|(idx, _poly)| idx)
+            .collect();
+
+        if nodes.is_empty() {
+            return Err(PinError::UnknownPiece(*letter));
+        }
 
-                let color = format!("{:x}", colors[poly.get_id()]).chars().last().unwrap();
-                
-                poly[pt_idx].set_color(color);
+        nodes.iter().for_each(|node| pinned[*node] = Some(color_idx));
+    }
+
+    let name = |node: usize| b.polyominoes[node].pentomino_name().unwrap_or('?');
+
+    for (a, c) in graph.pairs(options.adjacency) {
+        if pinned[a].is_some() && pinned[a] == pinned[c] {
+            return Err(PinError::NeighborsPinned(name(a), name(c)));
+        }
+    }
+
+    let fewest = pinned.iter().flatten().max().map_or(1, |c| c+1);
+
+    let colors = (fewest..=MAX_PINNED_COLORS)
+        .find_map(|count| extend_coloring(&graph, options.adjacency, &pinned, count))
+        .ok_or(PinError::NoColoring)?;
+
+    apply_coloring(b, &colors);
+
+    Ok(())
+}
+
+// Fills in the unpinned pieces with colors 0..count, most constrained
+// pieces first
+fn extend_coloring(graph: &AdjacencyGraph, adjacency: Adjacency, pinned: &Vec<Option<usize>>, count: usize) -> Option<Vec<usize>> {
+    let neighbors: Vec<Vec<usize>> = (0..graph.node_count()).map(|node| graph.neighbors(node, adjacency)).collect();
+
+    let mut order: Vec<usize> = (0..graph.node_count()).filter(|node| pinned[*node].is_none()).collect();
+    order.sort_by_key(|node| Reverse(neighbors[*node].len()));
+
+    let mut assignment = pinned.clone();
+
+    if assign_colors(0, &order, &neighbors, &mut assignment, count) {
+        Some(assignment.iter().map(|c| c.unwrap()).collect())
+    } else {
+        None
+    }
+}
+
+fn assign_colors(i: usize, order: &Vec<usize>, neighbors: &Vec<Vec<usize>>, assignment: &mut Vec<Option<usize>>, count: usize) -> bool {
+    if i == order.len() {
+        return true;
+    }
+
+    let node = order[i];
+
+    for color in 0..count {
+        if neighbors[node].iter().all(|n| assignment[*n] != Some(color)) {
+            assignment[node] = Some(color);
+            if assign_colors(i+1, order, neighbors, assignment, count) {
+                return true;
             }
         }
     }
+
+    assignment[node] = None;
+    false
+}
+
+impl fmt::Display for PinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PinError::UnknownPiece(letter) => write!(f, "There is no {} piece to pin", letter),
+            PinError::BadColor(color) => write!(f, "Can't pin a piece to color {}", color),
+            PinError::NeighborsPinned(a, b) => write!(f, "The {} and {} pieces touch, so they can't be pinned to the same color", a, b),
+            PinError::NoColoring => write!(f, "The pinned colors can't be extended to the whole board with {} colors", MAX_PINNED_COLORS)
+        }
+    }
 }
 
-fn find_coloring(b: &ColorableBoard) -> Vec<usize>{
-    let mut adjacencies = HashSet::new();
+/// Like color_board_with, but the pieces are colored greedily in a random
+/// order, so different seeds give different (valid) colorings. This
+/// usually takes more colors than color_board_with
+pub fn color_board_randomly(b: &mut ColorableBoard, adjacency: Adjacency, rng: &mut Rng) {
+    let colors: Vec<usize> = find_random_coloring(b, adjacency, rng);
+
+    apply_coloring(b, &colors);
+}
 
-    // set the id on all the polys
-    
+// Colors are by piece (index into the board's polyominoes)
+fn apply_coloring(b: &mut ColorableBoard, colors: &Vec<usize>) {
     for x in 0..b.width {
         for y in 0..b.height {
-            mark_as_adjacent(b, &b.get(x, y), &b.get(x+1, y), &mut adjacencies);
-            mark_as_adjacent(b, &b.get(x, y), &b.get(x, y+1), &mut adjacencies);
+            if let IndexedBoardState::Full(poly_idx, pt_idx, _x, _y) = b.get(x, y) {
+                let color = format!("{:x}", colors[poly_idx]).chars().last().unwrap();
+
+                b.get_polyomino_mut(poly_idx)[pt_idx].set_color(color);
+            }
         }
     }
+}
+
+fn find_coloring(b: &ColorableBoard, adjacency: Adjacency) -> Vec<usize>{
+    let adjacency_graph = b.adjacency_graph();
 
-    let mut graph = VecVecGraph::new(adjacencies.len());
-    for (poly1, poly2) in adjacencies {
+    let mut graph = VecVecGraph::new(adjacency_graph.node_count());
+    for (poly1, poly2) in adjacency_graph.pairs(adjacency) {
        graph.add_edge(poly1, poly2);
     }
 
-    let result = color_rlf(&graph);
-
-    result
+    color_rlf(&graph)
 }
 
-fn mark_as_adjacent(b: &ColorableBoard, b1: &IndexedBoardState, b2: &IndexedBoardState, adjacencies: &mut HashSet<(usize, usize)>) {
-    if let IndexedBoardState::Full(p1_idx, _pt, _x, _y) = b1 {
-        if let IndexedBoardState::Full(p2_idx, _pt, _x, _y) = b2 {
-            if b.get_polyomino(*p1_idx).get_id() != b.get_polyomino(*p2_idx).get_id() {
-                // To avoid adding an edge twice, normalize them
-                let edge = (cmp::min(b.get_polyomino(*p1_idx).get_id(), b.get_polyomino(*p2_idx).get_id()),
-                            cmp::max(b.get_polyomino(*p1_idx).get_id(), b.get_polyomino(*p2_idx).get_id()));
-                
-                adjacencies.insert(edge);
-            }
-        }
+fn find_random_coloring(b: &ColorableBoard, adjacency: Adjacency, rng: &mut Rng) -> Vec<usize> {
+    let graph = b.adjacency_graph();
+
+    let mut order: Vec<usize> = (0..graph.node_count()).collect();
+    let mut colors: Vec<Option<usize>> = vec![None; graph.node_count()];
+
+    // Fisher-Yates
+    for i in (1..order.len()).rev() {
+        order.swap(i, rng.below(i+1));
+    }
+
+    for node in order {
+        let neighbor_colors: HashSet<usize> = graph.neighbors(node, adjacency).iter()
+            .filter_map(|n| colors[*n])
+            .collect();
+
+        colors[node] = (0..).find(|c| !neighbor_colors.contains(c));
     }
+
+    colors.iter().map(|c| c.unwrap_or(0)).collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::boardcolorer::*;
+
+    // 00000
+    // 11112
+    // 12222  (0 is an I, 1 and 2 are both Ls)
+    fn build_board() -> ColorableBoard {
+        let ids = vec![0, 0, 0, 0, 0,
+                       1, 1, 1, 1, 2,
+                       1, 2, 2, 2, 2];
+
+        ColorableBoard::from_cells(5, 3, &ids.iter().map(|id| Some((*id, '0'))).collect())
+    }
+
+    #[test]
+    fn pinned() {
+        let mut b = build_board();
 
+        color_board_pinned(&mut b, &ColoringOptions { pins: vec![('I', '2')], ..Default::default() }).unwrap();
 
+        assert_eq!(b.get_color(0, 0), Some('2'));
+        assert!(b.get_color(0, 1) != Some('2'));
+        assert!(b.get_color(4, 1) != b.get_color(3, 1));
+    }
 
+    #[test]
+    fn bad_pins() {
+        let mut b = build_board();
 
+        let pin = |letter: char, color: char| ColoringOptions { pins: vec![(letter, color)], ..Default::default() };
 
+        assert_eq!(color_board_pinned(&mut b, &pin('L', '0')), Err(PinError::NeighborsPinned('L', 'L')));
+        assert_eq!(color_board_pinned(&mut b, &pin('X', '0')), Err(PinError::UnknownPiece('X')));
+        assert_eq!(color_board_pinned(&mut b, &pin('I', 'z')), Err(PinError::BadColor('z')));
+    }
+}
@@ -0,0 +1,260 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorableboard::IndexedBoardState;
+use crate::metrics::DifficultyMetrics;
+use crate::sheet::layout_pieces;
+use crate::sheet::svg_spaces;
+
+// A printed collection of puzzles. Each puzzle gets a page of its own
+// with its pieces taken apart (see sheet), an empty outline of the
+// rectangle they fill and a difficulty rating (see
+// DifficultyMetrics::stars). The answers come at the back, a few to a
+// page, outlined piece by piece.
+//
+// A book is either one HTML document, with a page break after each
+// page, or a separate SVG picture for each page
+
+pub struct BookPuzzle {
+    pub name: String,
+    pub puzzle: ColorableBoard,
+    pub metrics: DifficultyMetrics
+}
+
+const INSTRUCTIONS: &str = "Fill the rectangle with the pieces so that the colors make the same set of shapes.";
+
+// On SVG pages. HTML pages hold as many as fit
+const ANSWERS_PER_PAGE: usize = 4;
+
+// SVG elements drawn from 0,0 and how much room they take up
+struct Figure {
+    width: i32,
+    height: i32,
+    body: String
+}
+
+// Around a figure on its own, so the heavy lines along its edges aren't
+// cut in half
+const PADDING: i32 = 2;
+
+impl Figure {
+    fn to_svg(&self) -> String {
+        format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n{}</svg>\n",
+                self.width + 2 * PADDING, self.height + 2 * PADDING, self.placed(PADDING, PADDING))
+    }
+
+    fn placed(&self, left: i32, top: i32) -> String {
+        format!("<g transform=\"translate({},{})\">\n{}</g>\n", left, top, self.body)
+    }
+}
+
+/// Every file in the directory, in order of name. Hidden files are left
+/// out
+pub fn puzzle_files(dir: &str) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let visible = path.file_name().is_some_and(|name| !name.to_string_lossy().starts_with('.'));
+
+        if path.is_file() && visible {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// The file name without the directory or extension
+pub fn puzzle_name(path: &str) -> String {
+    Path::new(path).file_stem().map_or(path.to_string(), |stem| stem.to_string_lossy().to_string())
+}
+
+/// "★★☆☆☆" for two stars out of five
+pub fn difficulty_text(metrics: &DifficultyMetrics) -> String {
+    let stars = metrics.stars().min(5);
+    "\u{2605}".repeat(stars) + &"\u{2606}".repeat(5 - stars)
+}
+
+pub fn book_html(puzzles: &Vec<BookPuzzle>, cell_size: u32) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Puzzles</title>\n<style>\n");
+    html.push_str("  body { font-family: sans-serif; }\n");
+    html.push_str("  section { page-break-after: always; }\n");
+    html.push_str("  .answer { display: inline-block; margin: 0 2em 2em 0; }\n");
+    html.push_str("</style>\n</head>\n<body>\n");
+
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        html.push_str("<section class=\"puzzle\">\n");
+        html.push_str(&format!("<h2>{}</h2>\n", escape(&title(i, puzzle))));
+        html.push_str(&format!("<p class=\"difficulty\">Difficulty: {}</p>\n", difficulty_text(&puzzle.metrics)));
+        html.push_str(&format!("<p>{}</p>\n", INSTRUCTIONS));
+        html.push_str(&puzzle_figure(puzzle, cell_size).to_svg());
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("<section class=\"answers\">\n<h1>Answers</h1>\n");
+
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        html.push_str(&format!("<div class=\"answer\">\n<h3>{}</h3>\n", escape(&title(i, puzzle))));
+        html.push_str(&answer_figure(puzzle, cell_size).to_svg());
+        html.push_str("</div>\n");
+    }
+
+    html.push_str("</section>\n</body>\n</html>\n");
+    html
+}
+
+/// A page for each puzzle, then the answers
+pub fn book_svg_pages(puzzles: &Vec<BookPuzzle>, cell_size: u32) -> Vec<String> {
+    let line = cell_size as i32;
+    let mut pages = Vec::new();
+
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        let figure = puzzle_figure(puzzle, cell_size);
+        let lines = [(title(i, puzzle), 1.0), (format!("Difficulty: {}", difficulty_text(&puzzle.metrics)), 0.75), (INSTRUCTIONS.to_string(), 0.75)];
+
+        let mut body = String::new();
+        for (n, (text, scale)) in lines.iter().enumerate() {
+            body.push_str(&svg_text(text, line, line * (n as i32 + 2), (line as f64 * scale) as i32));
+        }
+
+        let top = line * (lines.len() as i32 + 2);
+        body.push_str(&figure.placed(line, top));
+
+        // Room for the instructions, which are about half a space wide a
+        // letter
+        let width = (figure.width + 2 * line).max(INSTRUCTIONS.len() as i32 * line / 2);
+        pages.push(Figure { width, height: top + figure.height + line, body }.to_svg());
+    }
+
+    for (page, chunk) in puzzles.chunks(ANSWERS_PER_PAGE).enumerate() {
+        let mut body = svg_text("Answers", line, line * 2, line);
+        let (mut width, mut top) = (0, line * 3);
+
+        for (j, puzzle) in chunk.iter().enumerate() {
+            let figure = answer_figure(puzzle, cell_size);
+
+            top += line;
+            body.push_str(&svg_text(&title(page * ANSWERS_PER_PAGE + j, puzzle), line, top, line * 3 / 4));
+            top += line / 2;
+            body.push_str(&figure.placed(line, top));
+
+            top += figure.height;
+            width = width.max(figure.width + 2 * line);
+        }
+
+        pages.push(Figure { width, height: top + line, body }.to_svg());
+    }
+
+    pages
+}
+
+fn title(index: usize, puzzle: &BookPuzzle) -> String {
+    format!("{}. {}", index + 1, puzzle.name)
+}
+
+// The pieces, and below them the rectangle they go in
+fn puzzle_figure(puzzle: &BookPuzzle, cell_size: u32) -> Figure {
+    let size = cell_size as i32;
+    let board = &puzzle.puzzle;
+    let sheet = layout_pieces(board, 2 * board.get_width());
+
+    let mut body = svg_spaces(sheet.width, sheet.height, |x, y| sheet.get(x, y).map(|(idx, pt)| (idx, pt.get_color())), cell_size, (0, 0));
+    let top = (sheet.height as i32 + 1) * size;
+    let (width, height) = (board.get_width() as i32 * size, board.get_height() as i32 * size);
+
+    // Faint lines for the spaces, under a heavy outline
+    for x in 1..board.get_width() as i32 {
+        body.push_str(&format!("  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"lightgray\" stroke-width=\"1\"/>\n", x * size, top, x * size, top + height));
+    }
+    for y in 1..board.get_height() as i32 {
+        body.push_str(&format!("  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"lightgray\" stroke-width=\"1\"/>\n", 0, top + y * size, width, top + y * size));
+    }
+    body.push_str(&format!("  <rect x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"3\"/>\n", top, width, height));
+
+    Figure { width: width.max(sheet.width as i32 * size), height: top + height, body }
+}
+
+fn answer_figure(puzzle: &BookPuzzle, cell_size: u32) -> Figure {
+    let size = cell_size as i32;
+    let board = &puzzle.puzzle;
+
+    let space = |x: i16, y: i16| match board.get(x, y) {
+        IndexedBoardState::Full(p_idx, pt_idx, _x, _y) => Some((p_idx, board.get_point(p_idx, pt_idx).get_color())),
+        _ => None
+    };
+
+    Figure {
+        width: board.get_width() as i32 * size,
+        height: board.get_height() as i32 * size,
+        body: svg_spaces(board.get_width(), board.get_height(), space, cell_size, (0, 0))
+    }
+}
+
+fn svg_text(text: &str, left: i32, baseline: i32, font_size: i32) -> String {
+    format!("<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\">{}</text>\n", left, baseline, font_size, escape(text))
+}
+
+// Puzzle names come from file names, which may have anything in them
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::*;
+    use crate::colorableboard::ColorableBoard;
+
+    // +-+-+-+
+    // |0 0|1|
+    // +-+-+ +
+    // |2|1 1|
+    // +-+-+-+
+    fn build_puzzle(name: &str) -> BookPuzzle {
+        let ids = vec![0, 0, 1, 2, 1, 1];
+        let puzzle = ColorableBoard::from_cells(3, 2, &ids.iter().map(|id| Some((*id, std::char::from_digit(*id as u32, 10).unwrap()))).collect());
+        let metrics = DifficultyMetrics { tilings: 40, explored_before_answer: Some(3), branching_factor: 2.0, forced_pieces: 1, near_misses: 1 };
+
+        BookPuzzle { name: name.to_string(), puzzle, metrics }
+    }
+
+    #[test]
+    fn html() {
+        let puzzles = vec![build_puzzle("first"), build_puzzle("R&D <2>")];
+        let html = book_html(&puzzles, 10);
+
+        assert_eq!(html.matches("<section class=\"puzzle\">").count(), 2);
+        assert_eq!(html.matches("<div class=\"answer\">").count(), 2);
+        assert!(html.contains("<h2>2. R&amp;D &lt;2&gt;</h2>"));
+        assert!(html.contains("Difficulty: \u{2605}\u{2605}\u{2605}\u{2606}\u{2606}"));
+
+        // The answers come after the last puzzle
+        assert!(html.rfind("class=\"puzzle\"").unwrap() < html.find("<h1>Answers</h1>").unwrap());
+
+        // The pieces and the answer are colored, the rectangle isn't
+        assert_eq!(html.matches("fill=\"red\"").count(), 2 * 2 * 2);
+        assert_eq!(html.matches("fill=\"none\"").count(), 2);
+    }
+
+    #[test]
+    fn svg_pages() {
+        let puzzles: Vec<BookPuzzle> = (0..5).map(|i| build_puzzle(&format!("p{}", i))).collect();
+        let pages = book_svg_pages(&puzzles, 10);
+
+        // A page for each puzzle and two pages of answers
+        assert_eq!(pages.len(), 7);
+        assert!(pages.iter().all(|page| page.starts_with("<svg")));
+        assert!(pages[5].contains(">Answers</text>"));
+        assert_eq!(pages[5].matches("<text").count(), 1 + 4);
+        assert!(pages[6].contains(">5. p4</text>"));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(puzzle_name("book/07-spiral.txt"), "07-spiral");
+        assert_eq!(puzzle_name("plain"), "plain");
+    }
+}
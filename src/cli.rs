@@ -0,0 +1,848 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use polycolorpuzzle::adjacency::Adjacency;
+use polycolorpuzzle::boardcolorer::ColoringOptions;
+use polycolorpuzzle::boardcolorer::PinError;
+use polycolorpuzzle::colorableboard::Borders;
+use polycolorpuzzle::colorpoint::color_from_name;
+use polycolorpuzzle::colorpoint::DISPLAY_COLORS;
+use polycolorpuzzle::colorpoint::OutputMode;
+use polycolorpuzzle::coloringstats::StatsFilter;
+use polycolorpuzzle::patterns::Pattern;
+use polycolorpuzzle::patterns::PatternError;
+use polycolorpuzzle::raster::RasterOptions;
+use polycolorpuzzle::repair::RepairOptions;
+use polycolorpuzzle::sampling::SampleOptions;
+use polycolorpuzzle::utils::Balance;
+use polycolorpuzzle::utils::Orientation;
+use polycolorpuzzle::utils::PieceColorRule;
+
+// Command line parsing. Every command takes named options ("--size 10x6")
+// rather than positional arguments, so they can come in any order
+
+pub const USAGE: &str = "Usage: polycolorpuzzle <command> [options]
+
+Commands:
+  list-nice --size WxH [--balance B] [--max-diagonal-contacts N]
+            [--max-piece-spread N] [--all-colors-touch true|false]
+        list the solutions of that size that can be nicely colored
+  show --size WxH --base N
+        print solution N colored
+  pair --size WxH --base N --target M [--orientation O] [--max-tilings T]
+        color solution M with solution N and check for suitability
+  search --size WxH --base N [--max-tilings T]
+        color every solution with solution N (in every orientation that
+        gives a different puzzle)
+        and summarize why pairs were rejected
+  sample --size WxH [--samples N] [--seconds T] [--seed S] [--max-tilings T]
+         [--adjacency A]
+        impose random colorings of random solutions on random targets
+        and list the ones with a unique answer (100 samples by default)
+  sample --size WxH --replay S [--adjacency A] [--max-tilings T]
+        repeat the sample with seed S exactly (give it the same
+        --adjacency, --max-tilings and piece rule as the sample)
+  pattern --size WxH (--pattern P | --bitmap F | --image F) [--target M]
+          [--band N] [--colors K] [--max-tilings T]
+        color solution M space by space with a pattern instead of
+        another solution, or every solution if there is no target
+  edit (--file F | --size WxH --base N --target M [--orientation O])
+        edit the colors of a puzzle file or a pair by hand
+  repair --size WxH --base N --target M [--orientation O]
+         [--iterations I] [--seed S] [--save F]
+        search for a recoloring of a pair with a unique answer
+  graph (--file F | --size WxH --base N)
+        print which pieces touch as a graphviz (DOT) graph
+  dual (--file F | --size WxH --base N)
+        print the pieces as a graphviz (DOT) graph labeled with their
+        shapes and filled with their colors
+  verify --file F [--max-tilings T]
+        check that a puzzle file has a unique answer
+  render --file F
+        print a puzzle file
+  pieces (--file F | --size WxH --base N --target M [--orientation O])
+         [--format terminal|text|svg] [--width N] [--cell-size N]
+        print the pieces of a puzzle laid out separately, N spaces
+        wide (default 20)
+  png (--file F | --size WxH --base N --target M [--orientation O])
+      --out P [--cell-size N] [--palette C=#RRGGBB,...]
+        save P-pieces.png (the pieces laid out in a row), P-tiling.png
+        and P-answer.png (outlined along the colors)
+  book --dir D --out P [--format html|svg] [--cell-size N]
+       [--max-tilings T]
+        make a book of the puzzle files in D with their answers at the
+        back, as an HTML file P or SVG pages P-001.svg, P-002.svg, ...
+        Puzzles without a unique answer are left out
+
+Options:
+  --orientation O      normal, 180, flip-h or flip-v (default normal)
+  --adjacency A        for commands that color a solution, which pieces
+                       must be different colors: edge (pieces that share
+                       an edge, the default), king (corners count too)
+                       or contact:N (pieces that share N or more unit
+                       edges; shorter contacts are ignored, not weighed)
+  --pin X=red,I=blue   for commands that color a solution, always give the
+                       pieces with these shapes these colors
+  --balance B          what makes a coloring nice: exact (the same number
+                       of spaces of every color, the default), within:K
+                       (every color within K spaces of an even share) or
+                       pieces (the same number of pieces of every color)
+  --max-diagonal-contacts N
+                       at most N places where pieces of the same color
+                       touch at a corner
+  --max-piece-spread N at most N more pieces of one color than another
+  --all-colors-touch B every color must touch every other color
+  --pattern P          checkerboard, rows, columns or rings
+  --bitmap F           a file with a row of colors (0-5) per line,
+                       '.' for spaces that keep their color
+  --image F            a PNG or PPM image with a pixel for each space.
+                       Pixels get the nearest color and transparent ones
+                       keep their color
+  --band N             how many spaces wide each band of a pattern is
+                       (default 1)
+  --colors K           how many colors the bands cycle through, up to 6
+                       (default 2). Checkerboards always have 2
+  --cell-size N        pixels per space in PNG and SVG pictures (default
+                       24)
+  --palette C=#RRGGBB  draw color C (a name like red, or a digit) in this
+                       color instead, e.g. red=#cc3333,blue=#3355aa
+  --min-colors N       every piece must have at least N colors (default 2)
+  --max-same-color N   no piece may have more than N spaces of one color
+  --max-tilings T      reject a pair that can be tiled more than T ways
+                       rather than checking every tiling
+  --output M           how to print boards and pieces: color (colored
+                       squares), plain (the color of each space as a
+                       digit) or letters (the letter of each piece).
+                       Plain unless printing to a terminal
+  --borders B          draw lines around the pieces (pieces, the default),
+                       around the regions of one color (colors) or both
+                       (both: lines between pieces of one color dotted)
+  --help               show this message";
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    BadValue { option: String, value: String },
+    OutOfRange { what: String, index: usize, count: usize },
+    ImpossibleArea { width: i16, height: i16, needed: usize },
+    NoSolutions { width: i16, height: i16 },
+    NoPolyominoes,
+    Coloring(PinError),
+    Pattern(PatternError),
+    Io { path: String, error: io::Error }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{} (try --help)", msg),
+            CliError::BadValue { option, value } => write!(f, "'{}' is not a valid value for --{}", value, option),
+            CliError::OutOfRange { what, index, count } => {
+                if *count == 0 {
+                    write!(f, "There is no {} solution {}: the board has no solutions", what, index)
+                } else {
+                    write!(f, "There is no {} solution {}: solutions are numbered 0 to {}", what, index, count-1)
+                }
+            }
+            CliError::ImpossibleArea { width, height, needed } =>
+                write!(f, "A {}x{} board has {} spaces, but the polyominoes cover exactly {}", width, height, (*width as i32) * (*height as i32), needed),
+            CliError::NoSolutions { width, height } => write!(f, "The polyominoes can't tile a {}x{} board", width, height),
+            CliError::NoPolyominoes => write!(f, "Can't find polyomino file"),
+            CliError::Coloring(error) => write!(f, "{}", error),
+            CliError::Pattern(error) => write!(f, "{}", error),
+            CliError::Io { path, error } => write!(f, "{}: {}", path, error)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardSize {
+    pub width: i16,
+    pub height: i16
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PairArgs {
+    pub size: BoardSize,
+    pub base: usize,
+    pub target: usize,
+    pub orientation: Orientation
+}
+
+pub enum PuzzleSource {
+    File(String),
+    Pair(PairArgs)
+}
+
+// A single board, either a solution or from a puzzle file
+pub enum BoardSource {
+    File(String),
+    Solution { size: BoardSize, base: usize }
+}
+
+// Where the colors of a pattern command come from
+pub enum PatternSource {
+    Pattern(Pattern),
+    Bitmap(String),
+    Image(String)
+}
+
+// How to print a sheet of pieces
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SheetFormat {
+    Terminal,
+    Text,
+    Svg
+}
+
+// How to make a puzzle book
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BookFormat {
+    Html,
+    Svg
+}
+
+pub enum Command {
+    Help,
+    ListNice { size: BoardSize, balance: Balance, filter: StatsFilter },
+    Show { size: BoardSize, base: usize },
+    Pair(PairArgs),
+    Search { size: BoardSize, base: usize },
+    Sample { size: BoardSize, options: SampleOptions, replay: Option<u64> },
+    Pattern { size: BoardSize, target: Option<usize>, source: PatternSource },
+    Edit(PuzzleSource),
+    Repair { pair: PairArgs, options: RepairOptions, save: Option<String> },
+    Graph(BoardSource),
+    Dual(BoardSource),
+    Verify { file: String },
+    Render { file: String },
+    Png { source: PuzzleSource, prefix: String, options: RasterOptions },
+    Pieces { source: PuzzleSource, format: SheetFormat, width: i16, cell_size: u32 },
+    Book { dir: String, out: String, format: BookFormat, cell_size: u32 }
+}
+
+pub struct Cli {
+    pub command: Command,
+    pub piece_rule: PieceColorRule,
+    pub max_tilings: Option<usize>,
+    pub coloring: ColoringOptions,
+    pub output: Option<OutputMode>,    // None to decide by where the output goes
+    pub borders: Borders
+}
+
+impl Cli {
+    fn help() -> Cli {
+        Cli { command: Command::Help, piece_rule: PieceColorRule::default(), max_tilings: None, coloring: ColoringOptions::default(), output: None, borders: Borders::default() }
+    }
+}
+
+// Options every command takes
+const PIECE_RULE_OPTIONS: [&str; 2] = ["min-colors", "max-same-color"];
+const OUTPUT_OPTIONS: [&str; 2] = ["output", "borders"];
+
+pub fn parse_args(args: &[String]) -> Result<Cli, CliError> {
+    let command_name = match args.get(1) {
+        None => return Ok(Cli::help()),
+        Some(name) => name.as_str()
+    };
+
+    let allowed: &[&str] = match command_name {
+        "help" | "--help" | "-h" => return Ok(Cli::help()),
+        "list-nice" => &["size", "adjacency", "pin", "balance", "max-diagonal-contacts", "max-piece-spread", "all-colors-touch"],
+        "show" => &["size", "base", "adjacency", "pin"],
+        "search" => &["size", "base", "adjacency", "pin", "max-tilings"],
+        "pair" => &["size", "base", "target", "orientation", "adjacency", "pin", "max-tilings"],
+        "sample" => &["size", "samples", "seconds", "seed", "max-tilings", "replay", "adjacency"],
+        "pattern" => &["size", "target", "pattern", "bitmap", "image", "band", "colors", "max-tilings"],
+        "edit" => &["file", "size", "base", "target", "orientation", "adjacency", "pin"],
+        "repair" => &["size", "base", "target", "orientation", "adjacency", "pin", "iterations", "seed", "save"],
+        "graph" | "dual" => &["file", "size", "base", "adjacency", "pin"],
+        "verify" => &["file", "max-tilings"],
+        "render" => &["file"],
+        "pieces" => &["file", "size", "base", "target", "orientation", "adjacency", "pin", "format", "width", "cell-size"],
+        "png" => &["file", "size", "base", "target", "orientation", "adjacency", "pin", "out", "cell-size", "palette"],
+        "book" => &["dir", "out", "format", "cell-size", "max-tilings"],
+        _ => return Err(CliError::Usage(format!("Unknown command '{}'", command_name)))
+    };
+
+    let options = match parse_options(&args[2..], allowed)? {
+        Some(options) => options,
+        None => return Ok(Cli::help())
+    };
+
+    let mut piece_rule = PieceColorRule::default();
+    if let Some(min_colors) = options.get("min-colors") {
+        piece_rule.min_colors = parse_number("min-colors", min_colors)?;
+    }
+    if let Some(max_same_color) = options.get("max-same-color") {
+        piece_rule.max_same_color = Some(parse_number("max-same-color", max_same_color)?);
+    }
+
+    let max_tilings = match options.get("max-tilings") {
+        Some(max) => Some(parse_number("max-tilings", max)?),
+        None => None
+    };
+
+    let command = match command_name {
+        "list-nice" => Command::ListNice { size: size_option(&options)?, balance: balance_option(&options)?, filter: stats_filter_options(&options)? },
+        "show" => Command::Show { size: size_option(&options)?, base: number_option(&options, "base")? },
+        "search" => Command::Search { size: size_option(&options)?, base: number_option(&options, "base")? },
+        "pair" => Command::Pair(pair_options(&options)?),
+        "sample" => {
+            let mut sample_options = SampleOptions { piece_rule, max_tilings, adjacency: adjacency_option(&options)?, ..Default::default() };
+            if options.contains_key("seconds") {
+                sample_options.time_budget = Some(Duration::from_secs(number_option(&options, "seconds")?));
+                sample_options.samples = None;
+            }
+            if options.contains_key("samples") {
+                sample_options.samples = Some(number_option(&options, "samples")?);
+            }
+            if options.contains_key("seed") {
+                sample_options.seed = number_option(&options, "seed")?;
+            }
+            let replay = match options.get("replay") {
+                Some(seed) => Some(parse_number("replay", seed)?),
+                None => None
+            };
+            Command::Sample { size: size_option(&options)?, options: sample_options, replay }
+        }
+        "pattern" => {
+            let target = match options.get("target") {
+                Some(target) => Some(parse_number("target", target)?),
+                None => None
+            };
+            Command::Pattern { size: size_option(&options)?, target, source: pattern_options(&options)? }
+        }
+        "edit" => Command::Edit(puzzle_source_options(&options)?),
+        "repair" => {
+            let mut repair_options = RepairOptions { piece_rule, ..Default::default() };
+            if options.contains_key("iterations") {
+                repair_options.iterations = number_option(&options, "iterations")?;
+            }
+            if let Some(seed) = options.get("seed") {
+                repair_options.seed = parse_number("seed", seed)?;
+            }
+            Command::Repair { pair: pair_options(&options)?, options: repair_options, save: options.get("save").cloned() }
+        }
+        "graph" => Command::Graph(board_source_options(&options)?),
+        "dual" => Command::Dual(board_source_options(&options)?),
+        "verify" => Command::Verify { file: string_option(&options, "file")? },
+        "png" => {
+            let raster_options = RasterOptions { palette: palette_option(&options)?, cell_size: cell_size_option(&options)? };
+            Command::Png { source: puzzle_source_options(&options)?, prefix: string_option(&options, "out")?, options: raster_options }
+        }
+        "pieces" => {
+            let format = match options.get("format").map(|f| f.as_str()) {
+                None | Some("terminal") => SheetFormat::Terminal,
+                Some("text") => SheetFormat::Text,
+                Some("svg") => SheetFormat::Svg,
+                Some(other) => return Err(CliError::BadValue { option: "format".to_string(), value: other.to_string() })
+            };
+            let width = match options.get("width") {
+                Some(width) => parse_number("width", width)?,
+                None => 20
+            };
+            Command::Pieces { source: puzzle_source_options(&options)?, format, width, cell_size: cell_size_option(&options)? }
+        }
+        "book" => {
+            let format = match options.get("format").map(|f| f.as_str()) {
+                None | Some("html") => BookFormat::Html,
+                Some("svg") => BookFormat::Svg,
+                Some(other) => return Err(CliError::BadValue { option: "format".to_string(), value: other.to_string() })
+            };
+            Command::Book { dir: string_option(&options, "dir")?, out: string_option(&options, "out")?, format, cell_size: cell_size_option(&options)? }
+        }
+        _ => Command::Render { file: string_option(&options, "file")? }
+    };
+
+    let coloring = ColoringOptions { adjacency: adjacency_option(&options)?, pins: pin_option(&options)? };
+
+    let output = match options.get("output").map(|o| o.as_str()) {
+        None => None,
+        Some("color") => Some(OutputMode::Color),
+        Some("plain") => Some(OutputMode::Plain),
+        Some("letters") => Some(OutputMode::Letters),
+        Some(other) => return Err(CliError::BadValue { option: "output".to_string(), value: other.to_string() })
+    };
+
+    let borders = match options.get("borders").map(|b| b.as_str()) {
+        None | Some("pieces") => Borders::Pieces,
+        Some("colors") => Borders::Colors,
+        Some("both") => Borders::Both,
+        Some(other) => return Err(CliError::BadValue { option: "borders".to_string(), value: other.to_string() })
+    };
+
+    Ok(Cli { command, piece_rule, max_tilings, coloring, output, borders })
+}
+
+// Turns "--name value" pairs into a map. Returns None if --help was
+// asked for
+// The options that change what a sample draws, for repeating it with
+// --replay. Defaults are left out
+pub fn replay_flags(options: &SampleOptions, seed: u64) -> String {
+    let mut flags = format!("--replay {}", seed);
+
+    match options.adjacency {
+        Adjacency::Edge => {}
+        Adjacency::King => flags.push_str(" --adjacency king"),
+        Adjacency::MinContact(min) => flags.push_str(&format!(" --adjacency contact:{}", min))
+    }
+    if options.piece_rule.min_colors != PieceColorRule::default().min_colors {
+        flags.push_str(&format!(" --min-colors {}", options.piece_rule.min_colors));
+    }
+    if let Some(max) = options.piece_rule.max_same_color {
+        flags.push_str(&format!(" --max-same-color {}", max));
+    }
+    if let Some(max) = options.max_tilings {
+        flags.push_str(&format!(" --max-tilings {}", max));
+    }
+
+    flags
+}
+
+fn parse_options(args: &[String], allowed: &[&str]) -> Result<Option<HashMap<String, String>>, CliError> {
+    let mut options = HashMap::new();
+    let mut remaining = args.iter();
+
+    while let Some(arg) = remaining.next() {
+        let name = match arg.strip_prefix("--") {
+            Some("help") => return Ok(None),
+            Some(name) => name,
+            None => return Err(CliError::Usage(format!("Unexpected argument '{}'", arg)))
+        };
+
+        if !allowed.contains(&name) && !PIECE_RULE_OPTIONS.contains(&name) && !OUTPUT_OPTIONS.contains(&name) {
+            return Err(CliError::Usage(format!("Unknown option '--{}'", name)));
+        }
+
+        match remaining.next() {
+            Some(value) => { options.insert(name.to_string(), value.clone()); }
+            None => return Err(CliError::Usage(format!("Option '--{}' needs a value", name)))
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value.parse::<T>().map_err(|_| CliError::BadValue { option: option.to_string(), value: value.to_string() })
+}
+
+fn string_option(options: &HashMap<String, String>, name: &str) -> Result<String, CliError> {
+    options.get(name).cloned().ok_or_else(|| CliError::Usage(format!("Missing option '--{}'", name)))
+}
+
+fn number_option<T: std::str::FromStr>(options: &HashMap<String, String>, name: &str) -> Result<T, CliError> {
+    parse_number(name, &string_option(options, name)?)
+}
+
+fn size_option(options: &HashMap<String, String>) -> Result<BoardSize, CliError> {
+    let value = string_option(options, "size")?;
+    let bad_size = || CliError::BadValue { option: "size".to_string(), value: value.clone() };
+
+    let (width, height) = value.split_once('x').ok_or_else(bad_size)?;
+    let width = width.parse::<i16>().map_err(|_| bad_size())?;
+    let height = height.parse::<i16>().map_err(|_| bad_size())?;
+
+    if width <= 0 || height <= 0 {
+        return Err(bad_size());
+    }
+
+    Ok(BoardSize { width, height })
+}
+
+fn board_source_options(options: &HashMap<String, String>) -> Result<BoardSource, CliError> {
+    match options.get("file") {
+        Some(file) => Ok(BoardSource::File(file.clone())),
+        None => Ok(BoardSource::Solution { size: size_option(options)?, base: number_option(options, "base")? })
+    }
+}
+
+fn puzzle_source_options(options: &HashMap<String, String>) -> Result<PuzzleSource, CliError> {
+    match options.get("file") {
+        Some(file) => Ok(PuzzleSource::File(file.clone())),
+        None => Ok(PuzzleSource::Pair(pair_options(options)?))
+    }
+}
+
+fn cell_size_option(options: &HashMap<String, String>) -> Result<u32, CliError> {
+    let cell_size = match options.get("cell-size") {
+        Some(cell_size) => parse_number("cell-size", cell_size)?,
+        None => RasterOptions::default().cell_size
+    };
+
+    if !(1..=256).contains(&cell_size) {
+        return Err(CliError::BadValue { option: "cell-size".to_string(), value: cell_size.to_string() });
+    }
+
+    Ok(cell_size)
+}
+
+fn adjacency_option(options: &HashMap<String, String>) -> Result<Adjacency, CliError> {
+    let value = match options.get("adjacency") {
+        None => return Ok(Adjacency::default()),
+        Some(value) => value
+    };
+
+    match value.as_str() {
+        "edge" => Ok(Adjacency::Edge),
+        "king" => Ok(Adjacency::King),
+        _ => match value.strip_prefix("contact:") {
+            Some(min) => Ok(Adjacency::MinContact(parse_number("adjacency", min)?)),
+            None => Err(CliError::BadValue { option: "adjacency".to_string(), value: value.clone() })
+        }
+    }
+}
+
+// "X=red,I=0" into [('X', '0'), ('I', '0')]
+fn pin_option(options: &HashMap<String, String>) -> Result<Vec<(char, char)>, CliError> {
+    let value = match options.get("pin") {
+        None => return Ok(Vec::new()),
+        Some(value) => value
+    };
+    let bad_pin = || CliError::BadValue { option: "pin".to_string(), value: value.clone() };
+
+    value.split(',')
+        .map(|pin| {
+            let (letter, color) = pin.split_once('=').ok_or_else(bad_pin)?;
+            let mut letters = letter.trim().chars();
+
+            match (letters.next(), letters.next(), color_from_name(color.trim())) {
+                (Some(letter), None, Some(color)) => Ok((letter.to_ascii_uppercase(), color)),
+                _ => Err(bad_pin())
+            }
+        })
+        .collect()
+}
+
+// "red=#cc3333,1=#3355aa" into {'0': (204, 51, 51), '1': (51, 85, 170)}
+fn palette_option(options: &HashMap<String, String>) -> Result<BTreeMap<char, (u8, u8, u8)>, CliError> {
+    let value = match options.get("palette") {
+        None => return Ok(BTreeMap::new()),
+        Some(value) => value
+    };
+    let bad_palette = || CliError::BadValue { option: "palette".to_string(), value: value.clone() };
+
+    value.split(',')
+        .map(|entry| {
+            let (color, rgb) = entry.split_once('=').ok_or_else(bad_palette)?;
+            let color = color_from_name(color.trim()).ok_or_else(bad_palette)?;
+            let hex = rgb.trim().strip_prefix('#').filter(|h| h.len() == 6).ok_or_else(bad_palette)?;
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i+2).ok_or_else(bad_palette)?, 16).map_err(|_| bad_palette());
+
+            Ok((color, (channel(0)?, channel(2)?, channel(4)?)))
+        })
+        .collect()
+}
+
+fn balance_option(options: &HashMap<String, String>) -> Result<Balance, CliError> {
+    let value = match options.get("balance") {
+        None => return Ok(Balance::default()),
+        Some(value) => value
+    };
+
+    match value.as_str() {
+        "exact" => Ok(Balance::Exact),
+        "pieces" => Ok(Balance::EqualPieces),
+        _ => match value.strip_prefix("within:") {
+            Some(tolerance) => Ok(Balance::Within(parse_number("balance", tolerance)?)),
+            None => Err(CliError::BadValue { option: "balance".to_string(), value: value.clone() })
+        }
+    }
+}
+
+fn stats_filter_options(options: &HashMap<String, String>) -> Result<StatsFilter, CliError> {
+    let mut filter = StatsFilter::default();
+
+    if options.contains_key("max-diagonal-contacts") {
+        filter.max_diagonal_contacts = Some(number_option(options, "max-diagonal-contacts")?);
+    }
+    if options.contains_key("max-piece-spread") {
+        filter.max_piece_spread = Some(number_option(options, "max-piece-spread")?);
+    }
+    if options.contains_key("all-colors-touch") {
+        filter.all_colors_touch = number_option(options, "all-colors-touch")?;
+    }
+
+    Ok(filter)
+}
+
+fn pattern_options(options: &HashMap<String, String>) -> Result<PatternSource, CliError> {
+    let band: i16 = match options.get("band") {
+        Some(band) => parse_number("band", band)?,
+        None => 1
+    };
+    let colors: usize = match options.get("colors") {
+        Some(colors) => parse_number("colors", colors)?,
+        None => 2
+    };
+
+    if band <= 0 {
+        return Err(CliError::BadValue { option: "band".to_string(), value: band.to_string() });
+    }
+    if !(1..=DISPLAY_COLORS.len()).contains(&colors) {
+        return Err(CliError::BadValue { option: "colors".to_string(), value: colors.to_string() });
+    }
+
+    let sources: Vec<&str> = ["pattern", "bitmap", "image"].into_iter().filter(|name| options.contains_key(*name)).collect();
+
+    match sources.as_slice() {
+        ["bitmap"] => Ok(PatternSource::Bitmap(options["bitmap"].clone())),
+        ["image"] => Ok(PatternSource::Image(options["image"].clone())),
+        ["pattern"] => match options["pattern"].as_str() {
+            "checkerboard" if options.contains_key("colors") => Err(CliError::Usage("A checkerboard always has 2 colors, leave out '--colors'".to_string())),
+            "checkerboard" => Ok(PatternSource::Pattern(Pattern::Checkerboard { band })),
+            "rows" => Ok(PatternSource::Pattern(Pattern::Rows { band, colors })),
+            "columns" => Ok(PatternSource::Pattern(Pattern::Columns { band, colors })),
+            "rings" => Ok(PatternSource::Pattern(Pattern::Rings { band, colors })),
+            other => Err(CliError::BadValue { option: "pattern".to_string(), value: other.to_string() })
+        },
+        [] => Err(CliError::Usage("Missing option '--pattern', '--bitmap' or '--image'".to_string())),
+        _ => Err(CliError::Usage("Use only one of '--pattern', '--bitmap' and '--image'".to_string()))
+    }
+}
+
+fn pair_options(options: &HashMap<String, String>) -> Result<PairArgs, CliError> {
+    let orientation = match options.get("orientation").map(|o| o.as_str()) {
+        None | Some("normal") => Orientation::Normal,
+        Some("180") => Orientation::OneEighty,
+        Some("flip-h") => Orientation::FlipHorizontally,
+        Some("flip-v") => Orientation::FlipOneEighty,
+        Some(other) => return Err(CliError::BadValue { option: "orientation".to_string(), value: other.to_string() })
+    };
+
+    Ok(PairArgs {
+        size: size_option(options)?,
+        base: number_option(options, "base")?,
+        target: number_option(options, "target")?,
+        orientation
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, CliError> {
+        let mut all_args = vec!["polycolorpuzzle".to_string()];
+        all_args.extend(args.iter().map(|a| a.to_string()));
+        parse_args(&all_args)
+    }
+
+    #[test]
+    fn pair() {
+        match parse(&["pair", "--target", "3", "--size", "10x6", "--base", "2", "--orientation", "180"]).unwrap().command {
+            Command::Pair(pair) => {
+                assert_eq!(pair.size, BoardSize { width: 10, height: 6 });
+                assert_eq!(pair.base, 2);
+                assert_eq!(pair.target, 3);
+                assert_eq!(pair.orientation, Orientation::OneEighty);
+            }
+            _ => panic!("Expected a pair command")
+        }
+    }
+
+    #[test]
+    fn piece_rule() {
+        let cli = parse(&["list-nice", "--size", "10x6", "--min-colors", "3"]).unwrap();
+        assert_eq!(cli.piece_rule.min_colors, 3);
+    }
+
+    #[test]
+    fn balance() {
+        match parse(&["list-nice", "--size", "10x6", "--balance", "within:2"]).unwrap().command {
+            Command::ListNice { balance, .. } => assert_eq!(balance, Balance::Within(2)),
+            _ => panic!("Expected a list-nice command")
+        }
+
+        assert!(matches!(parse(&["list-nice", "--size", "10x6", "--balance", "within:x"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["list-nice", "--size", "10x6", "--balance", "lopsided"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn stats_filter() {
+        match parse(&["list-nice", "--size", "10x6", "--max-diagonal-contacts", "2", "--all-colors-touch", "true"]).unwrap().command {
+            Command::ListNice { filter, .. } => {
+                assert_eq!(filter.max_diagonal_contacts, Some(2));
+                assert_eq!(filter.max_piece_spread, None);
+                assert!(filter.all_colors_touch);
+            }
+            _ => panic!("Expected a list-nice command")
+        }
+    }
+
+    #[test]
+    fn sample() {
+        match parse(&["sample", "--size", "10x6", "--seconds", "30", "--seed", "7", "--adjacency", "king"]).unwrap().command {
+            Command::Sample { options, replay, .. } => {
+                assert_eq!(options.adjacency, Adjacency::King);
+                assert_eq!(options.samples, None);
+                assert_eq!(options.time_budget, Some(Duration::from_secs(30)));
+                assert_eq!(options.seed, 7);
+                assert_eq!(replay, None);
+            }
+            _ => panic!("Expected a sample command")
+        }
+
+        // What it takes to draw the same sample again
+        let args = ["sample", "--size", "10x6", "--adjacency", "contact:2", "--min-colors", "3", "--max-tilings", "5"];
+        match parse(&args).unwrap().command {
+            Command::Sample { options, .. } => {
+                let flags = replay_flags(&options, 42);
+                assert_eq!(flags, "--replay 42 --adjacency contact:2 --min-colors 3 --max-tilings 5");
+
+                let replay_args: Vec<&str> = ["sample", "--size", "10x6"].into_iter().chain(flags.split(' ')).collect();
+                match parse(&replay_args).unwrap().command {
+                    Command::Sample { options: replayed, replay, .. } => {
+                        assert_eq!(replay, Some(42));
+                        assert_eq!(replayed.adjacency, options.adjacency);
+                        assert_eq!(replayed.piece_rule, options.piece_rule);
+                        assert_eq!(replayed.max_tilings, options.max_tilings);
+                    }
+                    _ => panic!("Expected a sample command")
+                }
+            }
+            _ => panic!("Expected a sample command")
+        }
+
+        assert_eq!(replay_flags(&SampleOptions::default(), 7), "--replay 7");
+    }
+
+    #[test]
+    fn pattern() {
+        match parse(&["pattern", "--size", "10x6", "--pattern", "rings", "--band", "2"]).unwrap().command {
+            Command::Pattern { target, source: PatternSource::Pattern(pattern), .. } => {
+                assert_eq!(target, None);
+                assert_eq!(pattern, Pattern::Rings { band: 2, colors: 2 });
+            }
+            _ => panic!("Expected a pattern command")
+        }
+
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--target", "3", "--bitmap", "p.txt"]).unwrap().command,
+                         Command::Pattern { target: Some(3), source: PatternSource::Bitmap(_), .. }));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--image", "p.png"]).unwrap().command,
+                         Command::Pattern { source: PatternSource::Image(_), .. }));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--image", "p.png", "--pattern", "rows"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--pattern", "spiral"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--pattern", "rows", "--band", "0"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--pattern", "rows", "--colors", "6"]).unwrap().command,
+                         Command::Pattern { source: PatternSource::Pattern(Pattern::Rows { colors: 6, .. }), .. }));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--pattern", "rows", "--colors", "7"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["pattern", "--size", "10x6", "--pattern", "checkerboard", "--colors", "3"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["pattern", "--size", "10x6"]), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn png() {
+        match parse(&["png", "--file", "p.txt", "--out", "p", "--cell-size", "10", "--palette", "red=#cc3333,1=#3355AA"]).unwrap().command {
+            Command::Png { source: PuzzleSource::File(_), prefix, options } => {
+                assert_eq!(prefix, "p");
+                assert_eq!(options.cell_size, 10);
+                assert_eq!(options.palette[&'0'], (0xcc, 0x33, 0x33));
+                assert_eq!(options.palette[&'1'], (0x33, 0x55, 0xaa));
+            }
+            _ => panic!("Expected a png command")
+        }
+
+        assert!(matches!(parse(&["png", "--file", "p.txt"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["png", "--file", "p.txt", "--out", "p", "--palette", "red=#cc33"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["png", "--file", "p.txt", "--out", "p", "--cell-size", "0"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn pieces() {
+        match parse(&["pieces", "--size", "10x6", "--base", "1", "--target", "2", "--format", "svg"]).unwrap().command {
+            Command::Pieces { source: PuzzleSource::Pair(pair), format, width, cell_size } => {
+                assert_eq!(pair.target, 2);
+                assert_eq!(format, SheetFormat::Svg);
+                assert_eq!(width, 20);
+                assert_eq!(cell_size, 24);
+            }
+            _ => panic!("Expected a pieces command")
+        }
+
+        assert!(matches!(parse(&["pieces", "--file", "p.txt", "--format", "html"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn book() {
+        let cli = parse(&["book", "--dir", "puzzles", "--out", "book", "--format", "svg", "--max-tilings", "500"]).unwrap();
+        assert_eq!(cli.max_tilings, Some(500));
+
+        match cli.command {
+            Command::Book { dir, out, format, cell_size } => {
+                assert_eq!((dir.as_str(), out.as_str()), ("puzzles", "book"));
+                assert_eq!(format, BookFormat::Svg);
+                assert_eq!(cell_size, 24);
+            }
+            _ => panic!("Expected a book command")
+        }
+
+        assert!(matches!(parse(&["book", "--dir", "puzzles"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["book", "--dir", "puzzles", "--out", "book", "--format", "pdf"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn output() {
+        assert_eq!(parse(&["render", "--file", "p.txt", "--output", "letters"]).unwrap().output, Some(OutputMode::Letters));
+        assert_eq!(parse(&["render", "--file", "p.txt"]).unwrap().output, None);
+        assert!(matches!(parse(&["render", "--file", "p.txt", "--output", "sepia"]), Err(CliError::BadValue { .. })));
+
+        assert_eq!(parse(&["pair", "--size", "10x6", "--base", "1", "--target", "2", "--borders", "colors"]).unwrap().borders, Borders::Colors);
+        assert_eq!(parse(&["render", "--file", "p.txt", "--borders", "both"]).unwrap().borders, Borders::Both);
+        assert_eq!(parse(&["render", "--file", "p.txt"]).unwrap().borders, Borders::Pieces);
+        assert!(matches!(parse(&["render", "--file", "p.txt", "--borders", "dashed"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn adjacency() {
+        assert_eq!(parse(&["show", "--size", "10x6", "--base", "0", "--adjacency", "king"]).unwrap().coloring.adjacency, Adjacency::King);
+        assert_eq!(parse(&["show", "--size", "10x6", "--base", "0", "--adjacency", "contact:2"]).unwrap().coloring.adjacency, Adjacency::MinContact(2));
+        assert_eq!(parse(&["show", "--size", "10x6", "--base", "0"]).unwrap().coloring.adjacency, Adjacency::Edge);
+        assert!(matches!(parse(&["show", "--size", "10x6", "--base", "0", "--adjacency", "queen"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn pins() {
+        let cli = parse(&["show", "--size", "10x6", "--base", "0", "--pin", "X=red,i=3"]).unwrap();
+        assert_eq!(cli.coloring.pins, vec![('X', '0'), ('I', '3')]);
+
+        assert!(matches!(parse(&["show", "--size", "10x6", "--base", "0", "--pin", "X"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["show", "--size", "10x6", "--base", "0", "--pin", "X=mauve"]), Err(CliError::BadValue { .. })));
+    }
+
+    #[test]
+    fn graph() {
+        assert!(matches!(parse(&["graph", "--file", "p.txt"]).unwrap().command, Command::Graph(BoardSource::File(_))));
+        assert!(matches!(parse(&["graph", "--size", "10x6", "--base", "4"]).unwrap().command, Command::Graph(BoardSource::Solution { base: 4, .. })));
+        assert!(matches!(parse(&["graph", "--size", "10x6"]), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn max_tilings() {
+        assert_eq!(parse(&["search", "--size", "10x6", "--base", "0", "--max-tilings", "500"]).unwrap().max_tilings, Some(500));
+        assert_eq!(parse(&["search", "--size", "10x6", "--base", "0"]).unwrap().max_tilings, None);
+        assert!(matches!(parse(&["show", "--size", "10x6", "--base", "0", "--max-tilings", "500"]), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn help() {
+        assert!(matches!(parse(&[]).unwrap().command, Command::Help));
+        assert!(matches!(parse(&["show", "--help"]).unwrap().command, Command::Help));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(parse(&["frobnicate"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["show", "--size", "10x6"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["show", "--size", "ten", "--base", "1"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["show", "--size", "10x6", "--base", "-1"]), Err(CliError::BadValue { .. })));
+        assert!(matches!(parse(&["show", "--size", "10x6", "--target", "1"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["show", "10", "6"]), Err(CliError::Usage(_))));
+
+        assert_eq!(CliError::NoSolutions { width: 7, height: 5 }.to_string(), "The polyominoes can't tile a 7x5 board");
+    }
+}
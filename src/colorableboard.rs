@@ -1,14 +1,20 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use colored::Colorize;
 
 use polyomino::board::Board;
 use polyomino::board::BoardState;
+use polyomino::point::Point;
 use polyomino::polyomino::Polyomino;
 
+use crate::adjacency::AdjacencyGraph;
 use crate::colorpoint::ColorPoint;
+use crate::colorpoint::cell_text;
 use crate::colorpoint::make_color;
+use crate::colorpoint::OutputMode;
 use crate::colorpolyomino::ColorPolyomino;
+use crate::utils::Orientation;
 
 // Similar to a polyomino::board::Board, but these references
 // to the underlying polyominoes are mutable so we can color them
@@ -39,7 +45,7 @@ impl IndexedBoardState {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct ColorableBoard {
     pub height: i16,
     pub width: i16,
@@ -74,7 +80,116 @@ impl ColorableBoard {
 
         mb
     }
+
+    /// Builds a board from the (polyomino id, color) of every cell, in
+    /// row order. Cells that belong to the same polyomino must have the
+    /// same id
+    pub fn from_cells(width: i16, height: i16, cells: &Vec<Option<(usize, char)>>) -> ColorableBoard {
+        let mut ids: Vec<usize> = Vec::new();
+        let mut points: Vec<Vec<(i16, i16, char)>> = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if let Some((id, color)) = cells[(x + y * width) as usize] {
+                    let poly_idx = match ids.iter().position(|i| *i == id) {
+                        Some(idx) => idx,
+                        None => {
+                            ids.push(id);
+                            points.push(Vec::new());
+                            ids.len()-1
+                        }
+                    };
+
+                    points[poly_idx].push((x, y, color));
+                }
+            }
+        }
+
+        let mut mb : ColorableBoard = ColorableBoard {
+            height,
+            width,
+            board: vec![IndexedBoardState::Empty; (height * width) as usize],
+            polyominoes: Vec::new()
+        };
+
+        for (poly_idx, poly_points) in points.iter().enumerate() {
+            let min_x = poly_points.iter().map(|(x, _y, _c)| *x).min().unwrap();
+            let min_y = poly_points.iter().map(|(_x, y, _c)| *y).min().unwrap();
+
+            let mut poly = ColorPolyomino::new(poly_points.iter().map(|(x, y, c)| ColorPoint::build_point(x-min_x, y-min_y, *c)).collect());
+            poly.set_id(ids[poly_idx]);
+
+            for (x, y, _c) in poly_points {
+                let pt_idx = poly.iter().position(|pt| pt.x() == x-min_x && pt.y() == y-min_y).unwrap();
+                let idx = mb.to_idx(*x, *y);
+                mb.board[idx] = IndexedBoardState::Full(poly_idx, pt_idx, min_x, min_y);
+            }
+
+            mb.polyominoes.push(poly);
+        }
+
+        mb
+    }
+
+    /// The same board, flipped and/or rotated
+    pub fn reoriented(&self, orientation: Orientation) -> ColorableBoard {
+        let mut cells = vec![None; (self.width * self.height) as usize];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let IndexedBoardState::Full(p, pt, _x, _y) = self.get(x, y) {
+                    let (ox, oy) = orientation.apply(x, y, self.width, self.height);
+                    cells[self.to_idx(ox, oy)] = Some((self.polyominoes[p].get_id(), self.get_point(p, pt).get_color()));
+                }
+            }
+        }
+
+        Self::from_cells(self.width, self.height, &cells)
+    }
     
+    /// Which pieces touch which. Compute it once and hang on to it
+    /// rather than calling this in a loop
+    pub fn adjacency_graph(&self) -> AdjacencyGraph {
+        AdjacencyGraph::new(self)
+    }
+
+    /// The orientations that leave every piece where it is (the
+    /// stabilizer of the tiling). Always includes Normal
+    pub fn symmetries(&self) -> Vec<Orientation> {
+        let placements = |b: &ColorableBoard| -> HashMap<usize, Vec<(i16, i16)>> {
+            b.piece_cells().into_iter()
+                .map(|(id, cells)| (id, cells.iter().map(|(x, y, _c)| (*x, *y)).collect()))
+                .collect()
+        };
+        let own = placements(self);
+
+        Orientation::all().into_iter()
+            .filter(|o| placements(&self.reoriented(*o)) == own)
+            .collect()
+    }
+
+    /// The orientations that leave the coloring the same up to renaming
+    /// the colors (the stabilizer of the coloring). The pieces don't
+    /// matter. Always includes Normal
+    pub fn coloring_symmetries(&self) -> Vec<Orientation> {
+        Orientation::all().into_iter()
+            .filter(|o| {
+                // Since o just moves spaces around, a consistent renaming
+                // is always one to one
+                let mut renaming: HashMap<char, char> = HashMap::new();
+
+                (0..self.height).all(|y| (0..self.width).all(|x| {
+                    let (ox, oy) = o.apply(x, y, self.width, self.height);
+                    match (self.get_color(x, y), self.get_color(ox, oy)) {
+                        (Some(color), Some(other)) => *renaming.entry(color).or_insert(other) == other,
+                        (None, None) => true,
+                        _ => false
+                    }
+                }))
+            })
+            .collect()
+    }
+
     pub fn get(&self, x: i16, y: i16) -> IndexedBoardState {
         if self.on_board(x, y) {
             self.board[self.to_idx(x, y)]
@@ -124,6 +239,25 @@ impl ColorableBoard {
     pub fn get_point(&self, poly_index: usize, pt_index: usize) -> &ColorPoint {
         self.polyominoes[poly_index].get_nth(pt_index).unwrap()
     }
+
+    /// The squares (and their colors) covered by each polyomino, keyed
+    /// by polyomino id. The colors are included so that a polyomino
+    /// flipped over in the same spot looks different
+    pub fn piece_cells(&self) -> HashMap<usize, Vec<(i16, i16, char)>> {
+        let mut cells: HashMap<usize, Vec<(i16, i16, char)>> = HashMap::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let IndexedBoardState::Full(p_idx, pt_idx, _x, _y) = self.get(x, y) {
+                    cells.entry(self.polyominoes[p_idx].get_id())
+                        .or_default()
+                        .push((x, y, self.get_point(p_idx, pt_idx).get_color()));
+                }
+            }
+        }
+
+        cells
+    }
     
     fn find_insert(v: &mut Vec<ColorPolyomino>, needle: &ColorPolyomino) -> usize {
         match v.iter().position(|p| p == needle) {
@@ -138,8 +272,79 @@ impl ColorableBoard {
     }
 }
 
-// Largely a copy of the polyomino code. Could be made generic if the board state were turned into a trait
+/// Which lines a board is drawn with. Lines around the regions of one
+/// color show the shapes the colors make, which is the answer. With both,
+/// color lines are drawn as usual and lines between pieces of the same
+/// color are dotted (':' and '.')
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Borders {
+    #[default]
+    Pieces,
+    Colors,
+    Both
+}
+
+// What is drawn between two neighboring spaces
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edge {
+    Open,
+    Dotted,
+    Solid
+}
+
+/// A way of displaying a board. The cursor, if there is one, is drawn
+/// as a hollow square in the color of the space under it (or '@' in the
+/// plain modes)
+pub struct BoardView<'a> {
+    pub board: &'a ColorableBoard,
+    pub cursor: Option<(i16, i16)>,
+    pub mode: OutputMode,
+    pub borders: Borders
+}
+
+impl ColorableBoard {
+    pub fn with_cursor(&self, x: i16, y: i16) -> BoardView<'_> {
+        BoardView { cursor: Some((x, y)), ..self.view(OutputMode::Color) }
+    }
+
+    pub fn view(&self, mode: OutputMode) -> BoardView<'_> {
+        BoardView { board: self, cursor: None, mode, borders: Borders::Pieces }
+    }
+}
+
 impl fmt::Display for ColorableBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.view(OutputMode::Color).fmt(f)
+    }
+}
+
+impl<'a> BoardView<'a> {
+    // The line between these two spaces. Testing equivalence should
+    // ignore the particular pt
+    fn edge(&self, (x1, y1): (i16, i16), (x2, y2): (i16, i16)) -> Edge {
+        let (a, b) = (self.board.get(x1, y1), self.board.get(x2, y2));
+
+        let same_piece = a.connected_to(b);
+        let same_color = match (a, b) {
+            (IndexedBoardState::Full(..), IndexedBoardState::Full(..)) => self.board.get_color(x1, y1) == self.board.get_color(x2, y2),
+            _ => same_piece
+        };
+
+        match self.borders {
+            Borders::Pieces if !same_piece => Edge::Solid,
+            Borders::Colors | Borders::Both if !same_color => Edge::Solid,
+            Borders::Both if !same_piece => Edge::Dotted,
+            _ => Edge::Open
+        }
+    }
+
+    fn joined(&self, a: (i16, i16), b: (i16, i16)) -> bool {
+        self.edge(a, b) == Edge::Open
+    }
+}
+
+// Largely a copy of the polyomino code. Could be made generic if the board state were turned into a trait
+impl<'a> fmt::Display for BoardView<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fn print_top_row_border(s: &ColorableBoard, f: &mut fmt::Formatter) -> fmt::Result {
             f.write_str(if s.get(0, 0) == IndexedBoardState::Void {
@@ -165,7 +370,9 @@ impl fmt::Display for ColorableBoard {
             f.write_str("\n")
         }
 
-        fn print_row(s: &ColorableBoard, f: &mut fmt::Formatter, y: i16) -> fmt::Result {
+        fn print_row(v: &BoardView, f: &mut fmt::Formatter, y: i16) -> fmt::Result {
+            let (s, cursor, mode) = (v.board, v.cursor, v.mode);
+
             for x in 0..s.width {
                 let piece = s.get(x, y);
 
@@ -173,25 +380,39 @@ impl fmt::Display for ColorableBoard {
                     f.write_str(if piece == IndexedBoardState::Void { " " } else { "|" })?;
                 }
 
-                write!(f, "{}",
-                       match piece {
-                           IndexedBoardState::Void => " ".black(),
-                           IndexedBoardState::Empty => ".".white(),
-                           IndexedBoardState::Full(p_idx, pt_idx, _x, _y) => {
-                               "\u{25A0}".color(make_color(s.get_point(p_idx, pt_idx).get_color()))
-                           }
-                       })?;
+                match (piece, mode) {
+                    (IndexedBoardState::Void, _) => f.write_str(" ")?,
+                    (IndexedBoardState::Empty, OutputMode::Color) => write!(f, "{}", ".".white())?,
+                    (IndexedBoardState::Empty, _) => f.write_str(".")?,
+                    (IndexedBoardState::Full(p_idx, pt_idx, _x, _y), _) => {
+                        let color = s.get_point(p_idx, pt_idx).get_color();
+
+                        if cursor == Some((x, y)) {
+                            match mode {
+                                OutputMode::Color => write!(f, "{}", "\u{25A1}".color(make_color(color)))?,
+                                _ => f.write_str("@")?
+                            }
+                        } else {
+                            f.write_str(&cell_text(color, s.polyominoes[p_idx].pentomino_name(), mode))?;
+                        }
+                    }
+                }
                         
-                // Testing equivalence should ignore the particular pt
-                f.write_str(if piece.connected_to(s.get(x + 1, y)) { " " } else { "|" })?;
+                f.write_str(match v.edge((x, y), (x + 1, y)) {
+                    Edge::Open => " ",
+                    Edge::Dotted => ":",
+                    Edge::Solid => "|"
+                })?;
             }
 
             f.write_str("\n")?;
 
-            print_row_bottom_border(s, f, y)
+            print_row_bottom_border(v, f, y)
         }
 
-        fn print_row_bottom_border(s: &ColorableBoard, f: &mut fmt::Formatter, y: i16) -> fmt::Result {
+        fn print_row_bottom_border(v: &BoardView, f: &mut fmt::Formatter, y: i16) -> fmt::Result {
+            let s = v.board;
+
             f.write_str(if s.get(0, y) == IndexedBoardState::Void {
                 " "
             } else {
@@ -199,25 +420,23 @@ impl fmt::Display for ColorableBoard {
             })?;
 
             for x in 0..s.width {
-                let piece = s.get(x, y);
-
-                f.write_str(if piece.connected_to(s.get(x, y + 1)) {
-                    if piece.connected_to(s.get(x + 1, y)) && s.get(x, y + 1).connected_to(s.get(x + 1, y + 1)) {
+                f.write_str(match v.edge((x, y), (x, y + 1)) {
+                    Edge::Open => if v.joined((x, y), (x + 1, y)) && v.joined((x, y + 1), (x + 1, y + 1)) {
                         "  "
                     } else {
                         " +"
-                    }
-                } else {
-                    "-+"
+                    },
+                    Edge::Dotted => ".+",
+                    Edge::Solid => "-+"
                 })?;
             }
 
             f.write_str("\n")
         }
 
-        print_top_row_border(self, f)?;
+        print_top_row_border(self.board, f)?;
 
-        for y in 0..self.height {
+        for y in 0..self.board.height {
             print_row(self, f, y)?;
         }
 
@@ -233,9 +452,12 @@ mod tests {
     use polyomino::point::SimplePoint;
     use polyomino::polyomino::Polyomino;
 
+    use crate::colorableboard::Borders;
     use crate::colorableboard::ColorableBoard;
     use crate::colorpolyomino::ColorPolyomino;
     use crate::colorpoint::ColorPoint;
+    use crate::colorpoint::OutputMode;
+    use crate::utils::Orientation;
     
     fn build_i() -> ColorPolyomino {
         let mut p = Vec::new();
@@ -323,6 +545,73 @@ mod tests {
         assert!(colorable_board.get(0, 0).get_poly_idx() != colorable_board.get(1, 0).get_poly_idx());
     }
 
+    #[test]
+    fn reoriented_one_eighty() {
+        let mut colorable_board = make_colorable_board();
+        colorable_board.polyominoes.iter_mut().enumerate().for_each(|(id, p)| p.set_id(id));
+        colorable_board.set_color(0, 0, '1');
+
+        let rotated = colorable_board.reoriented(Orientation::OneEighty);
+
+        assert_eq!(rotated.get_color(4, 4), Some('1'));
+        assert_eq!(rotated.get_color(4, 3), Some('0'));
+        assert_eq!(rotated.get_polyomino(rotated.get(4, 4).get_poly_idx().unwrap()).get_id(),
+                   colorable_board.get_polyomino(colorable_board.get(0, 0).get_poly_idx().unwrap()).get_id());
+        assert!(rotated.get(4, 4).get_poly_idx() == rotated.get(4, 3).get_poly_idx());
+    }
+
+    // +-+-+
+    // |a|b|
+    // + + +
+    // |b|a|
+    // +-+-+
+    #[test]
+    fn symmetric_board() {
+        let b = ColorableBoard::from_cells(2, 2, &vec![Some((0, 'a')), Some((1, 'b')), Some((0, 'b')), Some((1, 'a'))]);
+
+        assert_eq!(b.symmetries(), vec![Orientation::Normal, Orientation::FlipOneEighty]);
+        assert_eq!(b.coloring_symmetries(), Orientation::all());
+    }
+
+    #[test]
+    fn asymmetric_board() {
+        let mut colorable_board = make_colorable_board();
+        colorable_board.polyominoes.iter_mut().enumerate().for_each(|(id, p)| p.set_id(id));
+        colorable_board.set_color(0, 0, '1');
+
+        assert_eq!(colorable_board.symmetries(), vec![Orientation::Normal]);
+        assert_eq!(colorable_board.coloring_symmetries(), vec![Orientation::Normal]);
+    }
+
+    #[test]
+    fn plain_display() {
+        let b = ColorableBoard::from_cells(2, 1, &vec![Some((0, '0')), Some((1, '1'))]);
+
+        assert_eq!(b.view(OutputMode::Plain).to_string(), "+-+-+\n|0|1|\n+-+-+\n");
+
+        let mut cursor = b.with_cursor(1, 0);
+        cursor.mode = OutputMode::Plain;
+        assert_eq!(cursor.to_string(), "+-+-+\n|0|@|\n+-+-+\n");
+    }
+
+    // +-+-+-+
+    // |0 1|1|
+    // +-+-+ +
+    // |1|1 1|
+    // +-+-+-+
+    #[test]
+    fn borders() {
+        let b = ColorableBoard::from_cells(3, 2, &vec![Some((5, '0')), Some((5, '1')), Some((7, '1')),
+                                                       Some((6, '1')), Some((7, '1')), Some((7, '1'))]);
+        let mut view = b.view(OutputMode::Plain);
+
+        view.borders = Borders::Colors;
+        assert_eq!(view.to_string(), "+-+-+-+\n|0|1 1|\n+-+   +\n|1 1 1|\n+-+-+-+\n");
+
+        view.borders = Borders::Both;
+        assert_eq!(view.to_string(), "+-+-+-+\n|0|1:1|\n+-+.+ +\n|1:1 1|\n+-+-+-+\n");
+    }
+
     #[test]
     fn insert_different() {
         let mut v = Vec::new();
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorableboard::IndexedBoardState;
+
+// How a coloring looks, as opposed to how hard it is to solve (that is
+// in metrics). Colors are counted by the space and by the piece; a piece
+// with several colors counts once for each of them.
+//
+// * diagonal_contacts - pairs of spaces in different pieces that are the
+//   same color and touch only at a corner. These read as one blob at a
+//   glance, which spoils a coloring
+// * color_contacts - for every pair of colors, how many edges between
+//   different pieces have one color on each side. This is the graph of
+//   which color classes touch
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColoringStats {
+    pub cells: BTreeMap<char, usize>,
+    pub pieces: BTreeMap<char, usize>,
+    pub diagonal_contacts: usize,
+    pub color_contacts: BTreeMap<(char, char), usize>
+}
+
+pub fn coloring_stats(board: &ColorableBoard) -> ColoringStats {
+    let mut cells = BTreeMap::new();
+    let mut piece_colors: BTreeSet<(usize, char)> = BTreeSet::new();
+    let mut diagonal_contacts = 0;
+    let mut color_contacts = BTreeMap::new();
+
+    for y in 0..board.get_height() {
+        for x in 0..board.get_width() {
+            let (piece, color) = match (piece_at(board, x, y), board.get_color(x, y)) {
+                (Some(piece), Some(color)) => (piece, color),
+                _ => continue
+            };
+
+            *cells.entry(color).or_insert(0) += 1;
+            piece_colors.insert((piece, color));
+
+            // Only look right and down (and down-left) so every pair
+            // is seen once
+            for (nx, ny) in [(x+1, y), (x, y+1)] {
+                if let (Some(other_piece), Some(other_color)) = (piece_at(board, nx, ny), board.get_color(nx, ny)) {
+                    if other_piece != piece && other_color != color {
+                        *color_contacts.entry((color.min(other_color), color.max(other_color))).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            for (nx, ny) in [(x+1, y+1), (x-1, y+1)] {
+                if piece_at(board, nx, ny).is_some_and(|p| p != piece) && board.get_color(nx, ny) == Some(color) {
+                    diagonal_contacts += 1;
+                }
+            }
+        }
+    }
+
+    let mut pieces = BTreeMap::new();
+    piece_colors.iter().for_each(|(_piece, color)| *pieces.entry(*color).or_insert(0) += 1);
+
+    ColoringStats { cells, pieces, diagonal_contacts, color_contacts }
+}
+
+fn piece_at(board: &ColorableBoard, x: i16, y: i16) -> Option<usize> {
+    match board.get(x, y) {
+        IndexedBoardState::Full(p_idx, _pt, _x, _y) => Some(p_idx),
+        _ => None
+    }
+}
+
+impl ColoringStats {
+    /// Difference between the most and fewest pieces of any one color
+    pub fn piece_spread(&self) -> usize {
+        self.pieces.values().max().unwrap_or(&0) - self.pieces.values().min().unwrap_or(&0)
+    }
+
+    /// Every color touches every other color somewhere
+    pub fn all_colors_touch(&self) -> bool {
+        let n = self.cells.len();
+        self.color_contacts.len() == n * n.saturating_sub(1) / 2
+    }
+}
+
+/// Limits on the statistics of a coloring. The default lets everything
+/// through
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StatsFilter {
+    pub max_diagonal_contacts: Option<usize>,
+    pub max_piece_spread: Option<usize>,
+    pub all_colors_touch: bool
+}
+
+impl StatsFilter {
+    pub fn allows(&self, stats: &ColoringStats) -> bool {
+        self.max_diagonal_contacts.is_none_or(|max| stats.diagonal_contacts <= max) &&
+            self.max_piece_spread.is_none_or(|max| stats.piece_spread() <= max) &&
+            (!self.all_colors_touch || stats.all_colors_touch())
+    }
+}
+
+impl fmt::Display for ColoringStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Color  Spaces  Pieces")?;
+        for (color, count) in &self.cells {
+            writeln!(f, "{:>5}  {:>6}  {:>6}", color, count, self.pieces.get(color).unwrap_or(&0))?;
+        }
+        writeln!(f, "Same color diagonal contacts: {}", self.diagonal_contacts)?;
+        write!(f, "Color contacts:")?;
+        for ((a, b), count) in &self.color_contacts {
+            write!(f, " {}-{} ({})", a, b, count)?;
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colorableboard::ColorableBoard;
+    use crate::coloringstats::*;
+
+    // +-+-+
+    // |a|b|
+    // +-+-+
+    // |b|a|
+    // +-+-+
+    fn build_board() -> ColorableBoard {
+        ColorableBoard::from_cells(2, 2, &vec![Some((0, 'a')), Some((1, 'b')), Some((2, 'b')), Some((3, 'a'))])
+    }
+
+    #[test]
+    fn checkered() {
+        let stats = coloring_stats(&build_board());
+
+        assert_eq!(stats.cells[&'a'], 2);
+        assert_eq!(stats.pieces[&'b'], 2);
+        assert_eq!(stats.diagonal_contacts, 2);
+        assert_eq!(stats.color_contacts[&('a', 'b')], 4);
+        assert!(stats.all_colors_touch());
+        assert_eq!(stats.piece_spread(), 0);
+    }
+
+    #[test]
+    fn filter() {
+        let stats = coloring_stats(&build_board());
+
+        assert!(StatsFilter::default().allows(&stats));
+        assert!(!StatsFilter { max_diagonal_contacts: Some(1), ..Default::default() }.allows(&stats));
+        assert!(StatsFilter { max_piece_spread: Some(0), all_colors_touch: true, ..Default::default() }.allows(&stats));
+    }
+}
@@ -81,10 +81,38 @@ impl Hash for ColorPoint {
 
 impl fmt::Display for ColorPoint {
     fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", "\u{25A0}".color(make_color(self.color)))
+        write!(f, "{}", cell_text(self.color, None, OutputMode::Color))
     }
 }
 
+/// How spaces are printed. Colored squares are unreadable in log files
+/// and on terminals without color, so the other modes are plain ASCII:
+/// the color itself ('0', '1', ...) or the letter of the piece the space
+/// is part of (the color, where there is no piece to go by).
+///
+/// The mode goes on the views of boards, pieces and sheets. Plain Display
+/// is always Color
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputMode {
+    #[default]
+    Color,
+    Plain,
+    Letters
+}
+
+/// A space of this color (in the piece with this letter) the way the mode
+/// prints it
+pub fn cell_text(color: char, letter: Option<char>, mode: OutputMode) -> String {
+    match mode {
+        OutputMode::Color => "\u{25A0}".color(make_color(color)).to_string(),
+        OutputMode::Plain => color.to_string(),
+        OutputMode::Letters => letter.unwrap_or(color).to_string()
+    }
+}
+
+/// Every color that can be told apart on screen and in pictures. Any
+/// other color shows as black
+pub const DISPLAY_COLORS: &str = "012345";
 
 pub fn make_color(color: char) -> Color {
     match color {
@@ -92,10 +120,46 @@ pub fn make_color(color: char) -> Color {
         '1' => Color::Blue,
         '2' => Color::Yellow,
         '3' => Color::Green,
+        '4' => Color::Magenta,
+        '5' => Color::Cyan,
         _ => Color::Black
     }
 }
 
+/// The same colors by name, for things like graphviz and SVG that take
+/// color names
+pub fn color_name(color: char) -> &'static str {
+    match color {
+        '0' => "red",
+        '1' => "blue",
+        '2' => "yellow",
+        '3' => "green",
+        '4' => "magenta",
+        '5' => "cyan",
+        _ => "black"
+    }
+}
+
+/// The same colors as red, green, blue, for images
+pub fn make_rgb(color: char) -> (u8, u8, u8) {
+    match color {
+        '0' => (255, 0, 0),
+        '1' => (0, 0, 255),
+        '2' => (255, 255, 0),
+        '3' => (0, 128, 0),
+        '4' => (255, 0, 255),
+        '5' => (0, 255, 255),
+        _ => (0, 0, 0)
+    }
+}
+
+/// The other way: the color for a name ("red") or for the color itself
+/// ("0")
+pub fn color_from_name(name: &str) -> Option<char> {
+    "0123456789abcdef".chars().find(|c| name == color_name(*c) || name == c.to_string())
+        .filter(|c| color_name(*c) != "black")
+}
+
 #[allow(dead_code)]
 impl ColorPoint {
     pub fn build_point(x:i16, y:i16, color: char) -> ColorPoint {
@@ -113,8 +177,26 @@ impl ColorPoint {
 
 #[cfg(test)]
 mod tests {
+    use crate::colorpoint::cell_text;
+    use crate::colorpoint::color_from_name;
+    use crate::colorpoint::OutputMode;
     use crate::colorpoint::ColorPoint;
 
+    #[test]
+    fn names() {
+        assert_eq!(color_from_name("red"), Some('0'));
+        assert_eq!(color_from_name("2"), Some('2'));
+        assert_eq!(color_from_name("mauve"), None);
+    }
+
+    #[test]
+    fn plain_cells() {
+        assert_eq!(cell_text('3', Some('X'), OutputMode::Plain), "3");
+        assert_eq!(cell_text('3', Some('X'), OutputMode::Letters), "X");
+        assert_eq!(cell_text('3', None, OutputMode::Letters), "3");
+        assert!(cell_text('3', None, OutputMode::Color).contains('\u{25A0}'));
+    }
+
     #[test]
     fn pt_eq_self() {
         let p = ColorPoint::build_point(0, 0, '0');
@@ -2,9 +2,28 @@ use std::fmt;
 use std::ops::{Index, IndexMut};
 use std::slice::Iter;
 
+use polyomino::point::Point;
 use polyomino::polyomino::Polyomino;
 
+use crate::colorpoint::cell_text;
 use crate::colorpoint::ColorPoint;
+use crate::colorpoint::OutputMode;
+
+// The twelve pentominoes in one orientation each, by their usual letters
+const PENTOMINO_SHAPES: [(char, [(i16, i16); 5]); 12] = [
+    ('F', [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]),
+    ('I', [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)]),
+    ('L', [(0, 0), (0, 1), (0, 2), (0, 3), (1, 3)]),
+    ('N', [(1, 0), (1, 1), (0, 2), (1, 2), (0, 3)]),
+    ('P', [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]),
+    ('T', [(0, 0), (1, 0), (2, 0), (1, 1), (1, 2)]),
+    ('U', [(0, 0), (2, 0), (0, 1), (1, 1), (2, 1)]),
+    ('V', [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)]),
+    ('W', [(0, 0), (0, 1), (1, 1), (1, 2), (2, 2)]),
+    ('X', [(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)]),
+    ('Y', [(1, 0), (0, 1), (1, 1), (1, 2), (1, 3)]),
+    ('Z', [(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)])
+];
 
 #[derive(Debug, Hash, Clone, PartialEq, Eq)]
 pub struct ColorPolyomino {
@@ -57,11 +76,122 @@ impl ColorPolyomino {
         self.points.get_mut(nth)
     }
 
+    /// The usual letter for a pentomino of this shape, however it is
+    /// turned. None if it isn't a pentomino
+    pub fn pentomino_name(&self) -> Option<char> {
+        let shape = canonical_shape(&self.points.iter().map(|pt| (pt.x(), pt.y())).collect());
+
+        PENTOMINO_SHAPES.iter()
+            .find(|(_name, cells)| canonical_shape(&cells.to_vec()) == shape)
+            .map(|(name, _cells)| *name)
+    }
+
+    /// The piece turned so a pentomino sits the way it does in
+    /// PENTOMINO_SHAPES (anything else gets its canonical shape), moved to
+    /// 0,0. The colors turn with it. When several turns give the same
+    /// shape, the first one wins, so this is the same every time
+    pub fn canonical_orientation(&self) -> ColorPolyomino {
+        let cells: Vec<(i16, i16, char)> = self.points.iter().map(|pt| (pt.x(), pt.y(), pt.get_color())).collect();
+        let shape = |cells: &Vec<(i16, i16, char)>| -> Vec<(i16, i16)> {
+            let mut shape: Vec<(i16, i16)> = cells.iter().map(|(x, y, _color)| (*x, *y)).collect();
+            shape.sort();
+            shape
+        };
+
+        let wanted = match self.pentomino_name() {
+            Some(name) => {
+                let mut cells = PENTOMINO_SHAPES.iter().find(|(n, _cells)| *n == name).unwrap().1.to_vec();
+                cells.sort();
+                cells
+            }
+            None => canonical_shape(&shape(&cells))
+        };
+
+        let mut turned = cells;
+
+        for _ in 0..4 {
+            turned = turned.iter().map(|(x, y, color)| (-*y, *x, *color)).collect();
+
+            for candidate in [turned.clone(), turned.iter().map(|(x, y, color)| (-*x, *y, *color)).collect()] {
+                let min_x = candidate.iter().map(|c| c.0).min().unwrap_or(0);
+                let min_y = candidate.iter().map(|c| c.1).min().unwrap_or(0);
+                let moved: Vec<(i16, i16, char)> = candidate.iter().map(|(x, y, color)| (x - min_x, y - min_y, *color)).collect();
+
+                if shape(&moved) == wanted {
+                    let mut poly = ColorPolyomino::new(moved.iter().map(|(x, y, color)| ColorPoint::build_point(*x, *y, *color)).collect());
+                    poly.set_id(self.id);
+                    return poly;
+                }
+            }
+        }
+
+        self.clone()
+    }
+}
+
+// The smallest of the eight ways of turning the cells, moved to 0,0 and
+// sorted. Two shapes are the same if they have the same canonical shape
+fn canonical_shape(cells: &Vec<(i16, i16)>) -> Vec<(i16, i16)> {
+    let mut turned = cells.clone();
+    let mut best: Option<Vec<(i16, i16)>> = None;
+
+    for _ in 0..4 {
+        turned = turned.iter().map(|(x, y)| (-*y, *x)).collect();
+
+        for candidate in [turned.clone(), turned.iter().map(|(x, y)| (-*x, *y)).collect()] {
+            let min_x = candidate.iter().map(|(x, _y)| *x).min().unwrap_or(0);
+            let min_y = candidate.iter().map(|(_x, y)| *y).min().unwrap_or(0);
+
+            let mut normalized: Vec<(i16, i16)> = candidate.iter().map(|(x, y)| (x - min_x, y - min_y)).collect();
+            normalized.sort();
+
+            if best.as_ref().is_none_or(|b| normalized < *b) {
+                best = Some(normalized);
+            }
+        }
+    }
+
+    best.unwrap_or_default()
+}
+
+/// A way of displaying a piece: a grid of colored squares, the way
+/// boards show them (or in plain ASCII, see OutputMode)
+pub struct PolyominoView<'a> {
+    pub polyomino: &'a ColorPolyomino,
+    pub mode: OutputMode
+}
+
+impl ColorPolyomino {
+    pub fn view(&self, mode: OutputMode) -> PolyominoView<'_> {
+        PolyominoView { polyomino: self, mode }
+    }
 }
 
 impl fmt::Display for ColorPolyomino {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.show(f)
+        self.view(OutputMode::Color).fmt(f)
+    }
+}
+
+impl fmt::Display for PolyominoView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (piece, mode) = (self.polyomino, self.mode);
+        let letter = piece.pentomino_name();
+
+        let min_x = piece.points.iter().map(|pt| pt.x()).min().unwrap_or(0);
+        let min_y = piece.points.iter().map(|pt| pt.y()).min().unwrap_or(0);
+        let max_x = piece.points.iter().map(|pt| pt.x()).max().unwrap_or(0);
+        let max_y = piece.points.iter().map(|pt| pt.y()).max().unwrap_or(0);
+
+        for y in min_y..=max_y {
+            let row: Vec<String> = (min_x..=max_x)
+                .map(|x| piece.points.iter().find(|pt| pt.x() == x && pt.y() == y).map_or(" ".to_string(), |pt| cell_text(pt.get_color(), letter, mode)))
+                .collect();
+
+            writeln!(f, "{}", row.join(" ").trim_end())?;
+        }
+
+        Ok(())
     }
 }
 
@@ -156,6 +286,32 @@ mod tests {
         assert!(l == l.rotate().rotate().rotate().rotate());
     }
 
+    #[test]
+    fn names() {
+        assert_eq!(build_f_pentomino().pentomino_name(), Some('F'));
+        assert_eq!(build_l_pentomino().rotate().flip().pentomino_name(), Some('I'));
+
+        let mut v = Vec::new();
+        v.push(ColorPoint::build_point(0, 0, '0'));
+        v.push(ColorPoint::build_point(1, 0, '0'));
+        assert_eq!(ColorPolyomino::new(v).pentomino_name(), None);
+    }
+
+    #[test]
+    fn canonical() {
+        let f = build_f_pentomino();
+        let turned = f.rotate().flip().canonical_orientation();
+
+        assert_eq!(turned.canonical_orientation(), turned);
+        assert_eq!(turned.iter().map(|pt| (pt.x(), pt.y())).collect::<Vec<(i16, i16)>>(),
+                   vec![(0, 1), (1, 0), (1, 1), (1, 2), (2, 0)]);
+
+        // The I is upright, and the colors came along
+        let i = build_l_pentomino().rotate().canonical_orientation();
+        assert!(i.iter().all(|pt| pt.x() == 0));
+        assert_eq!(i.iter().filter(|pt| pt.get_color() == '0').count(), 1);
+    }
+
     #[test]
     fn count_l_variations() {
         let polys = vec![build_l_pentomino(), build_symmetric_l_pentomino()];
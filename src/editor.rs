@@ -0,0 +1,97 @@
+use std::cmp;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+use polyomino::polyomino::Polyomino;
+
+use crate::alternatives::solve_for_alternative_answers;
+use crate::colorableboard::BoardView;
+use crate::colorableboard::ColorableBoard;
+use crate::colorpoint::OutputMode;
+use crate::puzzlefile::save_puzzle;
+use crate::utils::*;
+
+// A simple line based editor for fixing up the colors of a board by
+// hand. Commands are read one line at a time from stdin
+
+fn print_help() {
+    println!("Commands:");
+    println!("  w a s d      move the cursor (can be repeated, e.g. 'ddd')");
+    println!("  0-9          set the color under the cursor");
+    println!("  check        check the board for a unique answer");
+    println!("  save <file>  save the board as a puzzle file");
+    println!("  help         show this message");
+    println!("  quit         stop editing");
+}
+
+pub fn edit<P: Polyomino>(board: &mut ColorableBoard, all_polyominoes: &Vec<Vec<P>>, piece_rule: &PieceColorRule, mode: OutputMode) {
+    let stdin = io::stdin();
+    let mut x: i16 = 0;
+    let mut y: i16 = 0;
+
+    print_help();
+
+    loop {
+        println!("\n{}", BoardView { cursor: Some((x, y)), ..board.view(mode) });
+        print!("({}, {}) color {} > ", x, y, board.get_color(x, y).unwrap_or('.'));
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            [] => {}
+            ["quit"] | ["q"] => break,
+            ["help"] | ["?"] => print_help(),
+            ["check"] | ["c"] => check(board, all_polyominoes, piece_rule),
+            ["save", path] => {
+                match save_puzzle(board, path) {
+                    Ok(()) => println!("Saved {}", path),
+                    Err(e) => println!("Unable to save {}: {}", path, e)
+                }
+            }
+            [moves] if moves.chars().all(|c| "wasd".contains(c)) => {
+                for c in moves.chars() {
+                    match c {
+                        'w' => y = cmp::max(y-1, 0),
+                        's' => y = cmp::min(y+1, board.get_height()-1),
+                        'a' => x = cmp::max(x-1, 0),
+                        _ => x = cmp::min(x+1, board.get_width()-1)
+                    }
+                }
+            }
+            [color] if color.len() == 1 && color.chars().all(|c| c.is_ascii_digit()) => {
+                board.set_color(x, y, color.chars().next().unwrap());
+            }
+            _ => println!("Unknown command. Type 'help' for a list of commands")
+        }
+    }
+}
+
+// The same checks as a base/target pair gets, but on the board as it
+// is right now
+fn check<P: Polyomino>(board: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>, piece_rule: &PieceColorRule) {
+    if !piece_rule.allows_all(board) {
+        println!("Warning: at least one piece breaks the rule ({})", piece_rule);
+    }
+
+    if !has_all_polyomino_patterns(board, all_polyominoes) {
+        println!("The colors on the board don't make every shape");
+        return;
+    }
+
+    println!("Solving...");
+    let alternatives = solve_for_alternative_answers(board, all_polyominoes);
+
+    if alternatives.is_empty() {
+        println!("Unique answer");
+    } else {
+        println!("{} alternative answer(s)\n", alternatives.len());
+        alternatives.iter().for_each(|a| println!("{}", a));
+    }
+}
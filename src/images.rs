@@ -0,0 +1,189 @@
+use std::fs;
+use std::io;
+
+use crate::colorpoint::make_rgb;
+use crate::colorpoint::DISPLAY_COLORS;
+use crate::patterns::Pattern;
+
+// Color pictures drawn in a paint program, one pixel per space. Each
+// pixel becomes the nearest of the colors we can display, and
+// transparent pixels keep the color the space had, like '.' in a bitmap.
+//
+// Images can be PNG or PPM (binary "P6" or plain text "P3"). The kind is
+// worked out from the first bytes of the file, not from its name
+
+// (red, green, blue, alpha) for each pixel, a row at a time
+struct Pixels {
+    width: usize,
+    height: usize,
+    rgba: Vec<(u8, u8, u8, u8)>
+}
+
+pub fn load_image(path: &str) -> io::Result<Pattern> {
+    image_from_bytes(&fs::read(path)?)
+}
+
+pub fn image_from_bytes(bytes: &[u8]) -> io::Result<Pattern> {
+    let pixels = if bytes.starts_with(b"\x89PNG") {
+        read_png(bytes)?
+    } else if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+        read_ppm(bytes)?
+    } else {
+        return Err(bad_data("Not a PNG or PPM image".to_string()));
+    };
+
+    let cells = pixels.rgba.iter()
+        .map(|(r, g, b, a)| if *a < 128 { None } else { Some(nearest_color(*r, *g, *b)) })
+        .collect();
+
+    Ok(Pattern::Bitmap { width: pixels.width as i16, height: pixels.height as i16, cells })
+}
+
+/// The displayable color closest to this one
+pub fn nearest_color(r: u8, g: u8, b: u8) -> char {
+    let distance = |color: char| {
+        let (cr, cg, cb) = make_rgb(color);
+        [(r, cr), (g, cg), (b, cb)].iter().map(|(p, c)| (*p as i32 - *c as i32).pow(2)).sum::<i32>()
+    };
+
+    DISPLAY_COLORS.chars().min_by_key(|c| distance(*c)).unwrap()
+}
+
+fn bad_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Checked as soon as the size is known, before anything that size is
+// allocated. Bitmaps are board sized, so anything past i16 is no use
+fn check_size(width: usize, height: usize) -> io::Result<()> {
+    if width == 0 || height == 0 || width > i16::MAX as usize || height > i16::MAX as usize {
+        return Err(bad_data(format!("Can't use a {}x{} image", width, height)));
+    }
+
+    Ok(())
+}
+
+fn read_png(bytes: &[u8]) -> io::Result<Pixels> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+    check_size(reader.info().width as usize, reader.info().height as usize)?;
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let data = &buf[..info.buffer_size()];
+
+    // Palettes are expanded and 16 bit samples stripped, so everything
+    // is down to one of these with a byte per sample
+    let rgba = match info.color_type {
+        png::ColorType::Grayscale => data.iter().map(|v| (*v, *v, *v, 255)).collect(),
+        png::ColorType::GrayscaleAlpha => data.chunks(2).map(|p| (p[0], p[0], p[0], p[1])).collect(),
+        png::ColorType::Rgb => data.chunks(3).map(|p| (p[0], p[1], p[2], 255)).collect(),
+        png::ColorType::Rgba => data.chunks(4).map(|p| (p[0], p[1], p[2], p[3])).collect(),
+        png::ColorType::Indexed => return Err(bad_data("Can't expand the PNG palette".to_string()))
+    };
+
+    Ok(Pixels { width: info.width as usize, height: info.height as usize, rgba })
+}
+
+// "P6 <width> <height> <maxval>" and then the pixels as bytes (two per
+// sample if maxval is over 255), or "P3" and the same numbers as text.
+// Comments run from '#' to the end of the line
+fn read_ppm(bytes: &[u8]) -> io::Result<Pixels> {
+    let mut pos = 0;
+    let mut header = Vec::new();
+
+    while header.len() < 4 {
+        match bytes.get(pos) {
+            None => return Err(bad_data("PPM header is cut short".to_string())),
+            Some(b'#') => while pos < bytes.len() && bytes[pos] != b'\n' { pos += 1 },
+            Some(c) if c.is_ascii_whitespace() => pos += 1,
+            Some(_) => {
+                let start = pos;
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'#' {
+                    pos += 1;
+                }
+                header.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+            }
+        }
+    }
+
+    let number = |i: usize| header[i].parse::<usize>().map_err(|_| bad_data(format!("Bad PPM header value '{}'", header[i])));
+    let (width, height, maxval) = (number(1)?, number(2)?, number(3)?);
+    check_size(width, height)?;
+
+    if maxval == 0 || maxval > 65535 {
+        return Err(bad_data(format!("Bad PPM maximum value {}", maxval)));
+    }
+
+    let samples: Vec<usize> = if header[0] == "P6" {
+        // Exactly one whitespace byte between the header and the pixels
+        let data = bytes.get(pos+1..).unwrap_or(&[]);
+        if maxval > 255 {
+            data.chunks_exact(2).map(|s| (s[0] as usize) << 8 | s[1] as usize).collect()
+        } else {
+            data.iter().map(|s| *s as usize).collect()
+        }
+    } else {
+        String::from_utf8_lossy(&bytes[pos..]).split_whitespace()
+            .map(|s| s.parse::<usize>().map_err(|_| bad_data(format!("Bad PPM sample '{}'", s))))
+            .collect::<io::Result<Vec<usize>>>()?
+    };
+
+    if samples.len() < width * height * 3 {
+        return Err(bad_data(format!("PPM has {} samples, not the {} a {}x{} image needs", samples.len(), width * height * 3, width, height)));
+    }
+
+    let scale = |s: usize| (s.min(maxval) * 255 / maxval) as u8;
+    let rgba = samples.chunks(3).take(width * height).map(|p| (scale(p[0]), scale(p[1]), scale(p[2]), 255)).collect();
+
+    Ok(Pixels { width, height, rgba })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::images::*;
+    use crate::patterns::PatternError;
+
+    #[test]
+    fn nearest() {
+        assert_eq!(nearest_color(250, 10, 10), '0');
+        assert_eq!(nearest_color(0, 255, 0), '3');
+        assert_eq!(nearest_color(240, 230, 20), '2');
+    }
+
+    #[test]
+    fn ppm() {
+        let text = "P3\n# A red, blue, green and yellow square\n2 2\n255\n255 0 0  0 0 255\n0 128 0  255 255 0\n";
+        let pattern = image_from_bytes(text.as_bytes()).unwrap();
+
+        assert_eq!(pattern, Pattern::Bitmap { width: 2, height: 2, cells: vec![Some('0'), Some('1'), Some('3'), Some('2')] });
+
+        let mut binary = b"P6 2 1 255\n".to_vec();
+        binary.extend([0, 255, 255, 255, 0, 255]);
+        assert_eq!(image_from_bytes(&binary).unwrap(), Pattern::Bitmap { width: 2, height: 1, cells: vec![Some('5'), Some('4')] });
+
+        assert!(image_from_bytes(b"P3 2 2 255 0 0 0").is_err());
+
+        // Too big to be a board, and width * height * 3 would overflow
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        assert_eq!(image_from_bytes(huge.as_bytes()).unwrap_err().to_string(), format!("Can't use a {}x3 image", usize::MAX / 2));
+        assert!(image_from_bytes(b"P3 0 2 255\n").is_err());
+        assert!(matches!(pattern.check_size(10, 6), Err(PatternError::SizeMismatch { pattern: (2, 2), board: (10, 6) })));
+    }
+
+    #[test]
+    fn png() {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[0, 0, 250, 255, 0, 0, 0, 0]).unwrap();
+        }
+
+        assert_eq!(image_from_bytes(&bytes).unwrap(), Pattern::Bitmap { width: 2, height: 1, cells: vec![Some('1'), None] });
+    }
+}
@@ -0,0 +1,58 @@
+//! Colored polyomino puzzles.
+//!
+//! A set of polyominoes is colored so that, when the pieces are put
+//! together in the right way, the colors themselves make a complete set
+//! of the same polyominoes. The colors come from tiling a rectangle, four
+//! coloring the tiling and imposing those colors on a different tiling
+//! of the same rectangle (see [`pipeline`]).
+//!
+//! * Coloring: [`boardcolorer::color_board`]
+//! * Overlaying one board's colors on another: [`utils::overlay`]
+//! * Validating: [`utils::has_all_polyomino_patterns`],
+//!   [`utils::PieceColorRule`], [`alternatives`] and [`metrics`]
+//! * Rendering: the `Display` impls of [`colorableboard::ColorableBoard`]
+//!   and [`colorpoint::ColorPoint`], and [`puzzlefile`] for saving and
+//!   loading puzzles
+
+/// Which pieces of a board touch, and DOT exports of that graph
+pub mod adjacency;
+/// Alternative answers to a colored board and how they differ from it
+pub mod alternatives;
+/// Puzzle books: a page for each puzzle and the answers at the back
+pub mod book;
+/// Coloring the pieces of a board so that neighbors are different colors
+pub mod boardcolorer;
+/// A solved board whose pieces can be colored space by space
+pub mod colorableboard;
+/// A point with a color
+pub mod colorpoint;
+/// How many spaces and pieces get each color and which colors touch
+pub mod coloringstats;
+/// A polyomino made of colored points
+pub mod colorpolyomino;
+/// An interactive editor for the colors of a board
+pub mod editor;
+/// Reading color pictures from PNG and PPM images
+pub mod images;
+/// Difficulty estimates for a colored set of pieces
+pub mod metrics;
+/// Colorings laid over a board space by space: stripes, rings and bitmaps
+pub mod patterns;
+/// Generating solutions, nicely colored boards and base/target pairs
+pub mod pipeline;
+/// Reading and writing puzzle files
+pub mod puzzlefile;
+/// PNG pictures of the pieces, tiling and answer of a puzzle
+pub mod raster;
+/// Searching for a recoloring of a board that has a unique answer
+pub mod repair;
+/// A small seedable random number generator
+pub mod rng;
+/// Trying random pairs when there are too many to try them all
+pub mod sampling;
+/// The pieces of a board laid out separately for publishing
+pub mod sheet;
+/// Printing several boards next to each other with captions
+pub mod sidebyside;
+/// Overlaying colors and finding color patterns on boards
+pub mod utils;
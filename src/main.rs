@@ -1,213 +1,392 @@
-mod colorpoint;
-mod colorpolyomino;
-mod colorableboard;
-mod boardcolorer;
-mod utils;
+mod cli;
 
 use std::env;
+use std::fs;
+use std::io;
+use std::io::IsTerminal;
+use std::process;
 
-use polyomino::board::Board;
 use polyomino::polyomino::Polyomino;
-use polyomino::solver::Solver;
 use polyomino::utils as poly_utils;
 use polyomino::utils::Restrictions;
-use polyomino::utils::PredefinedPolyominoes;
-
-use crate::colorpolyomino::ColorPolyomino;
-use crate::colorableboard::ColorableBoard;
-use crate::boardcolorer::color_board;
-use crate::utils::*;
-
-// This was originally going to be much more complicated
-//
-// The idea is to get a set of pentomioes that are colored and form
-// them into a rectange such that the colors themselves form a
-// complete set of pentominoes.
-//
-// Take a solution
-// Four color it (typically this can be done with three colors)
-// Overlay that solution on a different solution
-// That different solution now has a set of colored pentominoes
-// Use those pentominoes and see how many solutions there are such
-//   that there are a complete set of pentominoes in the colors
-//
-// The original idea was to do a search. Take every solution and apply
-// it to every other solution (cull out uninteresting cases where you
-// end up with a mono-colored pentomino on the target solution).
-//
-// This turns out to be unnecssary. As long as you pick two solutions such
-// that you don't end up with a mono-colored pentomino after the overlay,
-// you'll probaby end up with just one viable solution
-//
-// The only other issue is picking a board with a nice initial coloring
-// Everything can be four-colored (obivously), but the algorithm is too
-// good and usually (?) only one polyomino needs the fourth color
-//
-// find_nice_coloring will look for a coloring that is perfectly balanced
-// (a surprising number are) and then you are off to the races 
-
-struct Config {
-    pub xsize: i16,
-    pub ysize: i16,
-    pub base_solution_number: Option<usize>,
-    pub target_solution_number: Option<usize>
-}
+
+use polycolorpuzzle::adjacency::coloring_to_dot;
+use polycolorpuzzle::book::*;
+use polycolorpuzzle::boardcolorer::color_board_pinned;
+use polycolorpuzzle::boardcolorer::ColoringOptions;
+use polycolorpuzzle::colorableboard::BoardView;
+use polycolorpuzzle::colorableboard::Borders;
+use polycolorpuzzle::colorableboard::ColorableBoard;
+use polycolorpuzzle::coloringstats::coloring_stats;
+use polycolorpuzzle::colorpoint::OutputMode;
+use polycolorpuzzle::colorpolyomino::ColorPolyomino;
+use polycolorpuzzle::editor::edit;
+use polycolorpuzzle::images::load_image;
+use polycolorpuzzle::patterns::load_bitmap;
+use polycolorpuzzle::pipeline::*;
+use polycolorpuzzle::puzzlefile::load_puzzle;
+use polycolorpuzzle::puzzlefile::puzzle_to_string;
+use polycolorpuzzle::puzzlefile::save_puzzle;
+use polycolorpuzzle::raster::*;
+use polycolorpuzzle::repair::{repair, RepairOptions};
+use polycolorpuzzle::sampling::*;
+use polycolorpuzzle::sheet::layout_pieces;
+use polycolorpuzzle::sidebyside::{side_by_side, terminal_width};
+use polycolorpuzzle::utils::*;
+
+use crate::cli::*;
+
+// The command line front end. Everything interesting is in the library
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 3 {
-        println!("Usage:\npolycolorpuzzles xsize ysize [base solution #] [target solution #]\n");
-        println!("polycolorpuzzle xsize ysize\n\tenumerate boards of that size that can be nicely colored");
-        println!("polycolorpuzzle xsize ysize base\n\tprint out board 'base' colored");
-        println!("polycolorpuzzle xsize ysize base target\n\tcolor 'target' with 'base' and check for suitability");
-        return;
+    if let Err(e) = parse_args(&args).and_then(run) {
+        eprintln!("polycolorpuzzle: {}", e);
+        process::exit(1);
     }
+}
 
-    let xsize = args[1].parse::<i16>().unwrap();
-    let ysize = args[2].parse::<i16>().unwrap();
+fn run(cli: Cli) -> Result<(), CliError> {
+    // Colors only mean something on a terminal
+    let mode = cli.output.unwrap_or(if io::stdout().is_terminal() { OutputMode::Color } else { OutputMode::Plain });
+
+    let polyominoes = load_pentominoes()?;
+    let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+
+    match cli.command {
+        Command::Help => println!("{}", USAGE),
+        Command::ListNice { size, balance, filter } => {
+            let solutions = solve_board(size, &polyominoes, &all_polyominoes)?;
+            println!("Looking for colorings with {}", balance);
+            for nice in find_nice_colorings(&solutions, &cli.coloring, balance, &filter) {
+                println!("Solution {} has a nice {} coloring ({} diagonal contact(s), piece spread {})",
+                         nice.solution, nice.colors(), nice.stats.diagonal_contacts, nice.stats.piece_spread());
+            }
+        }
+        Command::Show { size, base } => {
+            let solutions = solve_board(size, &polyominoes, &all_polyominoes)?;
+            let base_solution = get_colored_base(&solutions, base, &cli.coloring)?;
+            println!("{}", base_solution.view(mode));
+            println!("{}", coloring_stats(&base_solution));
+        }
+        Command::Pair(pair) => {
+            let solutions = solve_board(pair.size, &polyominoes, &all_polyominoes)?;
+            let base_solution = get_colored_base(&solutions, pair.base, &cli.coloring)?;
+            let target_solution = get_solution(&solutions, pair.target, "target")?;
 
-    let base_solution_number = if args.len() >= 4 {
-        Some(args[3].parse::<usize>().unwrap())
-    } else {
-        None
-    };
+            let result = build_single_solution_variations(&base_solution, &target_solution, pair.orientation, &all_polyominoes, &cli.piece_rule, cli.max_tilings);
+            print_pair_result(Some(&base_solution), &result, mode, cli.borders);
+        }
+        Command::Search { size, base } => {
+            let solutions = solve_board(size, &polyominoes, &all_polyominoes)?;
+            let base_solution = get_solution(&solutions, base, "base")?;
 
-    let target_solution_number = if args.len() > 4 {
-        Some(args[4].parse::<usize>().unwrap())
-    } else {
-        None
-    };
+            let results = build_all_solution_variations(&base_solution, &solutions, &all_polyominoes, &cli.coloring, &cli.piece_rule, cli.max_tilings)
+                .map_err(CliError::Coloring)?;
 
-    let config = Config {xsize, ysize, base_solution_number, target_solution_number};
-    
-    if let Ok(mut polyominoes) = poly_utils::get_polyominoes::<ColorPolyomino>(PredefinedPolyominoes::Pentominoes) {
+            for (target, result) in &results {
+                println!("Target {} ({:?})", target, result.orientation);
+                print_pair_result(None, result, mode, cli.borders);
+            }
 
-        polyominoes.iter_mut().enumerate().for_each(|(id, p)| p.set_id(id));
-        
-        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+            println!("Summary\n{}", outcome_histogram(&results));
+        }
+        Command::Sample { size, options, replay } => {
+            let solutions = solve_board(size, &polyominoes, &all_polyominoes)?;
 
-        let mut b = get_board(&config);
+            if solutions.is_empty() {
+                return Err(CliError::NoSolutions { width: size.width, height: size.height });
+            }
 
-        let mut solver = Solver::new(&mut b, &all_polyominoes);
+            match replay {
+                Some(seed) => {
+                    let sample = draw_sample(&solutions, seed, &all_polyominoes, options.adjacency, &options.piece_rule, options.max_tilings);
+                    println!("Base {}, target {} ({:?})", sample.base, sample.target, sample.result.orientation);
+                    print_pair_result(None, &sample.result, mode, cli.borders);
+                }
+                None => {
+                    println!("Sampling (seed {})...", options.seed);
+                    let result = sample_pairs(&solutions, &all_polyominoes, &options);
+
+                    for hit in &result.hits {
+                        println!("Sample {}: base {}, target {} ({:?}) has a unique answer. Replay with {}",
+                                 hit.index, hit.base, hit.target, hit.result.orientation, replay_flags(&options, hit.seed));
+                    }
+
+                    println!("{} hit(s) in {} sample(s)\n{}", result.hits.len(), result.samples, result.histogram);
+                }
+            }
+        }
+        Command::Pattern { size, target, source } => {
+            let pattern = match source {
+                PatternSource::Pattern(pattern) => pattern,
+                PatternSource::Bitmap(path) => load_bitmap(&path).map_err(|error| CliError::Io { path: path.clone(), error })?,
+                PatternSource::Image(path) => load_image(&path).map_err(|error| CliError::Io { path: path.clone(), error })?
+            };
+
+            // Before solving, which takes a while
+            pattern.check_size(size.width, size.height).map_err(CliError::Pattern)?;
+            let solutions = solve_board(size, &polyominoes, &all_polyominoes)?;
+
+            match target {
+                Some(target) => {
+                    let target_solution = get_solution(&solutions, target, "target")?;
+                    let result = build_pattern_variation(&pattern, &target_solution, &all_polyominoes, &cli.piece_rule, cli.max_tilings)
+                        .map_err(CliError::Pattern)?;
+                    print_pair_result(None, &result, mode, cli.borders);
+                }
+                None => {
+                    let mut results = Vec::new();
+
+                    for (i, target_solution) in solutions.iter().enumerate() {
+                        let result = build_pattern_variation(&pattern, target_solution, &all_polyominoes, &cli.piece_rule, cli.max_tilings)
+                            .map_err(CliError::Pattern)?;
+
+                        if result.outcome == Outcome::Unique {
+                            println!("Target {} has a unique answer\n{}", i, result.target.view(mode));
+                        }
+                        results.push((i, result));
+                    }
+
+                    println!("Summary\n{}", outcome_histogram(&results));
+                }
+            }
+        }
+        Command::Edit(source) => {
+            let mut puzzle = read_puzzle_source(source, &cli.coloring, &polyominoes, &all_polyominoes)?;
 
-        println!("Generating solutions");
-        let solutions:Vec<ColorableBoard> = solver.solve().iter().map(|s| ColorableBoard::new(s)).collect();
+            edit(&mut puzzle, &all_polyominoes, &cli.piece_rule, mode);
+        }
+        Command::Repair { pair, options, save } => {
+            let target_solution = overlay_pair(&pair, &cli.coloring, &polyominoes, &all_polyominoes)?;
+
+            if let Some(repaired) = repair_target(&target_solution, &all_polyominoes, &options, mode) {
+                if let Some(path) = save {
+                    save_puzzle(&repaired, &path).map_err(|error| CliError::Io { path: path.clone(), error })?;
+                    println!("Saved {}", path);
+                }
+            }
+        }
+        Command::Graph(source) => {
+            let board = read_board(source, &cli.coloring, &polyominoes, &all_polyominoes)?;
+            print!("{}", board.adjacency_graph().to_dot());
+        }
+        Command::Dual(source) => {
+            let board = read_board(source, &cli.coloring, &polyominoes, &all_polyominoes)?;
+            print!("{}", coloring_to_dot(&board));
+        }
+        Command::Verify { file } => {
+            let puzzle = read_puzzle(&file)?;
+            let boards = vec![("Puzzle".to_string(), &puzzle)];
+
+            match verify_colored_target(&puzzle, &all_polyominoes, &cli.piece_rule, cli.max_tilings) {
+                Ok(verification) => print_verification(boards, &verification, mode, cli.borders),
+                Err(outcome) => {
+                    print_boards(&boards, mode, cli.borders);
+                    println!("{}", outcome);
+                }
+            }
+        }
+        Command::Render { file } => {
+            let puzzle = read_puzzle(&file)?;
+            let mut view = puzzle.view(mode);
+            view.borders = cli.borders;
+            println!("{}", view);
+        }
+        Command::Png { source, prefix, options } => {
+            let puzzle = read_puzzle_source(source, &cli.coloring, &polyominoes, &all_polyominoes)?;
+
+            for (name, canvas) in [("pieces", pieces_image(&puzzle, &options)),
+                                   ("tiling", tiling_image(&puzzle, &options)),
+                                   ("answer", answer_image(&puzzle, &options))] {
+                let path = format!("{}-{}.png", prefix, name);
+                canvas.save_png(&path).map_err(|error| CliError::Io { path: path.clone(), error })?;
+                println!("Saved {}", path);
+            }
+        }
+        Command::Pieces { source, format, width, cell_size } => {
+            let puzzle = read_puzzle_source(source, &cli.coloring, &polyominoes, &all_polyominoes)?;
+            let sheet = layout_pieces(&puzzle, width);
+
+            match format {
+                SheetFormat::Terminal => print!("{}", sheet.view(mode)),
+                SheetFormat::Text => print!("{}", sheet.to_text()),
+                SheetFormat::Svg => print!("{}", sheet.to_svg(cell_size))
+            }
+        }
+        Command::Book { dir, out, format, cell_size } => {
+            let files = puzzle_files(&dir).map_err(|error| CliError::Io { path: dir.clone(), error })?;
+            let mut puzzles = Vec::new();
+
+            for file in &files {
+                // Anything else in the directory (a README, say) is
+                // skipped like a puzzle that fails to verify
+                let puzzle = match read_puzzle(file) {
+                    Ok(puzzle) => puzzle,
+                    Err(e) => {
+                        eprintln!("Leaving out {}", e);
+                        continue;
+                    }
+                };
+                eprintln!("Verifying {}", file);
+
+                // Only puzzles that can go in a book, in case one slipped
+                // in unverified
+                match verify_colored_target(&puzzle, &all_polyominoes, &cli.piece_rule, cli.max_tilings) {
+                    Ok(verification) if verification.is_unique() =>
+                        puzzles.push(BookPuzzle { name: puzzle_name(file), puzzle, metrics: verification.metrics }),
+                    Ok(verification) => eprintln!("Leaving out {}: {}", file, verification.outcome()),
+                    Err(outcome) => eprintln!("Leaving out {}: {}", file, outcome)
+                }
+            }
 
-        if config.base_solution_number.is_none() {
-            find_nice_colorings(&solutions);
-        } else if config.target_solution_number.is_none() {
-            let mut base_solution = solutions[config.base_solution_number.unwrap()].clone();
-            color_board(&mut base_solution);
-            println!("{}", base_solution);
-        } else {
-            let mut base_solution = solutions[config.base_solution_number.unwrap()].clone();
-            color_board(&mut base_solution);
-            
-            let target_solution = &mut solutions[config.target_solution_number.unwrap()].clone();
-            
-            build_single_solution_variations(&config, &base_solution, target_solution, Orientation::Normal, &all_polyominoes);
-        }
-    } else {
-        panic!("Can't find polyomino file");
+            let pages = match format {
+                BookFormat::Html => vec![(out.clone(), book_html(&puzzles, cell_size))],
+                BookFormat::Svg => book_svg_pages(&puzzles, cell_size).into_iter().enumerate()
+                    .map(|(i, page)| (format!("{}-{:03}.svg", out, i+1), page))
+                    .collect()
+            };
+
+            for (path, page) in pages {
+                fs::write(&path, page).map_err(|error| CliError::Io { path: path.clone(), error })?;
+                println!("Saved {}", path);
+            }
+            println!("{} of {} puzzle(s) in the book", puzzles.len(), files.len());
+        }
     }
-}
 
-fn get_board<'a>(config: &Config) -> Board<'a, ColorPolyomino> {
-    Board::new(config.xsize, config.ysize)
+    Ok(())
 }
 
-// Looks for a coloring that is well balanced.
-fn find_nice_colorings(solutions: &Vec<ColorableBoard>) {
-    for (i, soln) in solutions.iter().enumerate() {
-        let mut base_solution = soln.clone();
-        color_board(&mut base_solution);
-        
-        let cnt = color_count(&base_solution);
-        
-        if cnt.len() == 3 && cnt.iter().all(|v| *v == 4*5) {
-            println!("Solution {} has a nice 3 coloring", i);
-        } else if cnt.iter().all(|v| *v == 3*5) {
-            println!("Solution {} has a nice 4 coloring", i);
+// The base (if there is one), the target and its answers side by side
+fn print_pair_result(base: Option<&ColorableBoard>, result: &PairResult, mode: OutputMode, borders: Borders) {
+    let mut boards: Vec<(String, &ColorableBoard)> = base.iter().map(|b| ("Base solution".to_string(), *b)).collect();
+    boards.push(("Target solution".to_string(), &result.target));
+
+    match &result.verification {
+        Some(verification) => print_verification(boards, verification, mode, borders),
+        None => {
+            print_boards(&boards, mode, borders);
+            println!("{}. Try a different one", result.outcome);
         }
     }
 }
 
-fn build_all_solution_variations<P: Polyomino>(config: &Config, base_solution: &ColorableBoard, all_solutions: &Vec<ColorableBoard>, all_polyominoes: &Vec<Vec<P>>) {
-    let mut colored_solution: ColorableBoard = base_solution.clone();
-            
-    color_board(&mut colored_solution);
-    
-    for target_soln in all_solutions {
-        let mut target_soln_mut: ColorableBoard = target_soln.clone();
-        
-        build_single_solution_variations(config, &colored_solution, &mut target_soln_mut, Orientation::Normal, &all_polyominoes);
-        
-        build_single_solution_variations(config, &colored_solution, &mut target_soln_mut, Orientation::OneEighty, &all_polyominoes);
-        
-        build_single_solution_variations(config, &colored_solution, &mut target_soln_mut, Orientation::FlipHorizontally, &all_polyominoes);
-        
-        build_single_solution_variations(config, &colored_solution, &mut target_soln_mut, Orientation::FlipOneEighty, &all_polyominoes);
+// The boards with the answers next to them (if there aren't too many),
+// then what was found out about them
+fn print_verification<'a>(mut boards: Vec<(String, &'a ColorableBoard)>, verification: &'a Verification, mode: OutputMode, borders: Borders) {
+    if verification.answers.len() < 10 {
+        boards.extend(verification.answers.iter().enumerate().map(|(i, answer)| (format!("Answer {}", i+1), answer)));
     }
-}
+    print_boards(&boards, mode, borders);
 
-fn build_single_solution_variations<P: Polyomino>(config: &Config, base_solution: &ColorableBoard, target_solution: &mut ColorableBoard, orientation: Orientation, all_polyominoes: &Vec<Vec<P>>) {
-    println!("Base solution\n{}", base_solution);
-    
-    overlay(base_solution, target_solution, orientation);
-
-    println!("Target solution\n{}", target_solution);
-    
-    // After imposing a coloring on another board, if any of the
-    // polyominoes on that board is mono-colored, we reject the
-    // solution as being boring
-    if !has_single_color_polyomino(&target_solution) {
-
-        // Take the polyominoes that make up this solution and build the variations of those
-        let colored_polys = poly_utils::build_variations(&target_solution.polyominoes,
-                                                         Restrictions::RectangularSymmetry);
-
-        // Build solutions with those polyominos
-        let mut board = get_board(config);
-        let mut solver = Solver::new(&mut board, &colored_polys);
-
-        println!("Solving...");
-        let solutions = solver.solve();
-
-        println!("Found {} posible solutions. Checking...", solutions.len());
-        
-        // Now check to see if any of those solutions has a connected
-        // blob of color in the pattern of every polyomino
-
-        let mut valid_solution_variations = Vec::new();
-        
-        for solution in solutions {
-            let colorable_solution = ColorableBoard::new(solution);
-            if has_all_polyomino_patterns(&colorable_solution, &all_polyominoes) {
-                valid_solution_variations.push(colorable_solution.clone());
-            }
-        }
+    println!("Found {} posible solutions", verification.tilings);
+    println!("\n{}", verification.metrics);
 
-        if !valid_solution_variations.is_empty() {
-            println!("\n\n{} valid solution(s)", valid_solution_variations.len());
+    if !verification.answers.is_empty() {
+        println!("\n\n{} valid solution(s)", verification.answers.len());
 
-            if valid_solution_variations.len() < 10 {
-                valid_solution_variations.iter().for_each(|s| println!("{}", s));
-            }
+        if verification.is_unique() {
+            println!("Unique answer");
+        } else {
+            println!("{} alternative answer(s)\n", verification.alternatives.len());
+            verification.alternatives.iter().for_each(|a| println!("{}", a));
         }
-    } else {
-        println!("Target has mono-colored polyomino. Try a different one");
     }
 }
 
+fn print_boards(boards: &[(String, &ColorableBoard)], mode: OutputMode, borders: Borders) {
+    let views: Vec<(String, BoardView)> = boards.iter()
+        .map(|(caption, board)| {
+            let mut view = board.view(mode);
+            view.borders = borders;
+            (caption.clone(), view)
+        })
+        .collect();
+
+    print!("{}", side_by_side(&views, terminal_width()));
+}
+
+fn load_pentominoes() -> Result<Vec<ColorPolyomino>, CliError> {
+    get_pentominoes().map_err(|_| CliError::NoPolyominoes)
+}
+
+// Every way of tiling a board of the given size with the polyominoes
+fn solve_board(size: BoardSize, polyominoes: &Vec<ColorPolyomino>, all_polyominoes: &Vec<Vec<ColorPolyomino>>) -> Result<Vec<ColorableBoard>, CliError> {
+    let needed = polyomino_area(polyominoes);
+
+    if (size.width as usize) * (size.height as usize) != needed {
+        return Err(CliError::ImpossibleArea { width: size.width, height: size.height, needed });
+    }
+
+    // Progress goes to stderr so output like DOT graphs can be piped
+    eprintln!("Generating solutions");
+    Ok(generate_solutions(size.width, size.height, all_polyominoes))
+}
+
+fn get_solution(solutions: &Vec<ColorableBoard>, index: usize, what: &str) -> Result<ColorableBoard, CliError> {
+    solutions.get(index)
+        .cloned()
+        .ok_or(CliError::OutOfRange { what: what.to_string(), index, count: solutions.len() })
+}
 
+fn get_colored_base(solutions: &Vec<ColorableBoard>, index: usize, coloring: &ColoringOptions) -> Result<ColorableBoard, CliError> {
+    let mut base_solution = get_solution(solutions, index, "base")?;
+    color_board_pinned(&mut base_solution, coloring).map_err(CliError::Coloring)?;
+    Ok(base_solution)
+}
 
+// The target of the pair with the coloring of the base imposed on it
+fn overlay_pair(pair: &PairArgs, coloring: &ColoringOptions, polyominoes: &Vec<ColorPolyomino>, all_polyominoes: &Vec<Vec<ColorPolyomino>>) -> Result<ColorableBoard, CliError> {
+    let solutions = solve_board(pair.size, polyominoes, all_polyominoes)?;
+    let base_solution = get_colored_base(&solutions, pair.base, coloring)?;
+    let mut target_solution = get_solution(&solutions, pair.target, "target")?;
 
+    overlay(&base_solution, &mut target_solution, pair.orientation);
 
+    Ok(target_solution)
+}
 
+fn read_puzzle(path: &str) -> Result<ColorableBoard, CliError> {
+    load_puzzle(path).map_err(|error| CliError::Io { path: path.to_string(), error })
+}
 
+fn read_puzzle_source(source: PuzzleSource, coloring: &ColoringOptions, polyominoes: &Vec<ColorPolyomino>, all_polyominoes: &Vec<Vec<ColorPolyomino>>) -> Result<ColorableBoard, CliError> {
+    match source {
+        PuzzleSource::File(path) => read_puzzle(&path),
+        PuzzleSource::Pair(pair) => overlay_pair(&pair, coloring, polyominoes, all_polyominoes)
+    }
+}
 
+fn read_board(source: BoardSource, coloring: &ColoringOptions, polyominoes: &Vec<ColorPolyomino>, all_polyominoes: &Vec<Vec<ColorPolyomino>>) -> Result<ColorableBoard, CliError> {
+    match source {
+        BoardSource::File(path) => read_puzzle(&path),
+        BoardSource::Solution { size, base } => {
+            let solutions = solve_board(size, polyominoes, all_polyominoes)?;
+            get_colored_base(&solutions, base, coloring)
+        }
+    }
+}
 
+fn repair_target<P: Polyomino>(target_solution: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>, options: &RepairOptions, mode: OutputMode) -> Option<ColorableBoard> {
+    println!("Target solution\n{}", target_solution.view(mode));
 
+    if !options.piece_rule.allows_all(target_solution) {
+        println!("Target has a piece that breaks the rule ({}). Try a different one", options.piece_rule);
+        return None;
+    }
 
+    println!("Repairing (seed {})...", options.seed);
+    let result = repair(target_solution, all_polyominoes, options);
 
+    println!("Tried {} changes. Alternative answers went from {} to {}",
+             result.changes_tried, result.starting_alternatives, result.alternatives);
+    println!("{}", result.board.view(mode));
+    match puzzle_to_string(&result.board) {
+        Ok(text) => println!("{}", text),
+        Err(e) => eprintln!("{}", e)
+    }
+
+    Some(result.board)
+}
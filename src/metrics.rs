@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+use polyomino::polyomino::Polyomino;
+
+use crate::colorableboard::ColorableBoard;
+use crate::utils::count_polyomino_patterns;
+
+// Rough measures of how hard a colored set of polyominoes is to solve
+// by hand. Everything is derived from the complete list of tilings of
+// the colored pieces, in the order the search found them (see
+// find_colored_tilings_up_to).
+//
+// * explored_before_answer - how many tilings the search went through
+//   before it hit one with every pattern in the colors
+// * branching_factor - on average, how many different places (and
+//   orientations) a piece occupies over all the tilings
+// * forced_pieces - pieces that sit in the same place in every tiling
+// * near_misses - tilings that show all but one of the patterns
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifficultyMetrics {
+    pub tilings: usize,
+    pub explored_before_answer: Option<usize>,
+    pub branching_factor: f64,
+    pub forced_pieces: usize,
+    pub near_misses: usize
+}
+
+pub fn difficulty_metrics<P: Polyomino>(tilings: &Vec<ColorableBoard>, all_polyominoes: &Vec<Vec<P>>) -> DifficultyMetrics {
+    let mut explored_before_answer = None;
+    let mut near_misses = 0;
+    let mut placements: HashMap<usize, HashSet<Vec<(i16, i16, char)>>> = HashMap::new();
+
+    for (i, tiling) in tilings.iter().enumerate() {
+        let found = count_polyomino_patterns(tiling, all_polyominoes);
+
+        if found == all_polyominoes.len() {
+            if explored_before_answer.is_none() {
+                explored_before_answer = Some(i);
+            }
+        } else if found + 1 == all_polyominoes.len() {
+            near_misses += 1;
+        }
+
+        for (id, placement) in tiling.piece_cells() {
+            placements.entry(id).or_default().insert(placement);
+        }
+    }
+
+    let branching_factor = if placements.is_empty() {
+        0.0
+    } else {
+        placements.values().map(|p| p.len()).sum::<usize>() as f64 / placements.len() as f64
+    };
+
+    DifficultyMetrics {
+        tilings: tilings.len(),
+        explored_before_answer,
+        branching_factor,
+        forced_pieces: placements.values().filter(|p| p.len() == 1).count(),
+        near_misses
+    }
+}
+
+impl fmt::Display for DifficultyMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Tilings of the colored pieces: {}", self.tilings)?;
+        match self.explored_before_answer {
+            Some(n) => writeln!(f, "Tilings explored before the answer: {}", n)?,
+            None => writeln!(f, "Tilings explored before the answer: no answer found")?
+        }
+        writeln!(f, "Average placements per piece: {:.2}", self.branching_factor)?;
+        writeln!(f, "Forced pieces: {}", self.forced_pieces)?;
+        writeln!(f, "Near misses (all but one shape): {}", self.near_misses)
+    }
+}
+
+impl DifficultyMetrics {
+    /// One to five stars, for printing with a puzzle. A star for the
+    /// puzzle itself, and one more for each of these
+    ///
+    /// * more than 10 tilings to sort through
+    /// * more than 100
+    /// * near misses, which look right until the last shape
+    /// * no forced pieces to start from
+    pub fn stars(&self) -> usize {
+        1 + [self.tilings > 10, self.tilings > 100, self.near_misses > 0, self.forced_pieces == 0].iter()
+            .filter(|harder| **harder)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polyomino::board::Board;
+    use polyomino::point::Point;
+    use polyomino::point::SimplePoint;
+    use polyomino::polyomino::Polyomino;
+    use polyomino::polyomino::SimplePolyomino;
+    use polyomino::utils as poly_utils;
+    use polyomino::utils::Restrictions;
+
+    use crate::colorableboard::ColorableBoard;
+    use crate::colorpoint::ColorPoint;
+    use crate::colorpolyomino::ColorPolyomino;
+    use crate::metrics::*;
+
+    fn build_bar(x: i16) -> ColorPolyomino {
+        let mut p = ColorPolyomino::new((0..5).map(|y| ColorPoint::new(0, y)).collect());
+        p.set_id(x as usize);
+        p
+    }
+
+    fn make_tiling() -> ColorableBoard {
+        let bars: Vec<ColorPolyomino> = (0..2).map(build_bar).collect();
+        let mut b = Board::new(2, 5);
+
+        b.add_polyomino(&bars[0], &SimplePoint::new(0, 0));
+        b.add_polyomino(&bars[1], &SimplePoint::new(1, 0));
+
+        ColorableBoard::new(&b)
+    }
+
+    #[test]
+    fn same_tiling_twice_is_forced() {
+        let tilings = vec![make_tiling(), make_tiling()];
+        let no_patterns: Vec<Vec<SimplePolyomino<SimplePoint>>> = Vec::new();
+
+        let metrics = difficulty_metrics(&tilings, &no_patterns);
+
+        assert_eq!(metrics.tilings, 2);
+        assert_eq!(metrics.explored_before_answer, Some(0));
+        assert_eq!(metrics.forced_pieces, 2);
+        assert_eq!(metrics.branching_factor, 1.0);
+        assert_eq!(metrics.near_misses, 0);
+        assert_eq!(metrics.stars(), 1);
+    }
+
+    // +-+-+-+     +-+-+-+
+    // |0 0|1|     |0|1 1|
+    // + +-+ +     + +-+ +
+    // |0|1 1|     |0 0|1|
+    // +-+-+-+     +-+-+-+
+    //
+    // the same two L trominoes either way round
+    fn make_colored_tiling(ids: [usize; 6], colors: &str) -> ColorableBoard {
+        ColorableBoard::from_cells(3, 2, &ids.iter().zip(colors.chars()).map(|(id, color)| Some((*id, color))).collect())
+    }
+
+    #[test]
+    fn answer_after_near_miss() {
+        let shapes = vec![SimplePolyomino::new(vec![SimplePoint::new(0, 0), SimplePoint::new(1, 0)]),
+                          SimplePolyomino::new(vec![SimplePoint::new(0, 0), SimplePoint::new(1, 0), SimplePoint::new(0, 1)])];
+        let patterns = poly_utils::build_variations(&shapes, Restrictions::None);
+
+        let tilings = vec![
+            // No pattern in the colors (a pattern has to be a whole
+            // region of one color)
+            make_colored_tiling([0, 0, 1, 0, 1, 1], "012345"),
+            // Only the domino, which makes it a near miss
+            make_colored_tiling([0, 0, 1, 0, 1, 1], "012012"),
+            // The domino and the L, which is the answer
+            make_colored_tiling([0, 1, 1, 0, 0, 1], "001022")
+        ];
+
+        let metrics = difficulty_metrics(&tilings, &patterns);
+
+        assert_eq!(metrics.tilings, 3);
+        assert_eq!(metrics.explored_before_answer, Some(2));
+        assert_eq!(metrics.near_misses, 1);
+
+        // Each piece is colored differently every time, so nothing is forced
+        assert_eq!(metrics.forced_pieces, 0);
+        assert_eq!(metrics.branching_factor, 3.0);
+        assert_eq!(metrics.stars(), 3);
+
+        // Without the answer there is nothing to explore up to
+        assert_eq!(difficulty_metrics(&tilings[..2].to_vec(), &patterns).explored_before_answer, None);
+    }
+
+    #[test]
+    fn stars() {
+        let metrics = DifficultyMetrics { tilings: 250, explored_before_answer: Some(40), branching_factor: 3.5, forced_pieces: 0, near_misses: 2 };
+        assert_eq!(metrics.stars(), 5);
+
+        let metrics = DifficultyMetrics { tilings: 12, forced_pieces: 3, near_misses: 0, ..metrics };
+        assert_eq!(metrics.stars(), 2);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorpoint::DISPLAY_COLORS;
+
+// Colorings of the spaces of a board that don't come from coloring the
+// pieces of a tiling. A pattern is laid over a target space by space,
+// so the hidden picture doesn't have to be a tiling itself (although it
+// still has to show every shape to be a puzzle).
+//
+// Bands are `band` spaces wide and the striped patterns cycle through
+// `colors` colors.
+//
+// A bitmap is a text file with a row of colors per line, '.' for spaces
+// that keep the color they had
+//
+// 0011
+// 0.11
+//
+// or an image with a pixel per space (see images)
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    Checkerboard { band: i16 },
+    Rows { band: i16, colors: usize },
+    Columns { band: i16, colors: usize },
+    Rings { band: i16, colors: usize },
+    Bitmap { width: i16, height: i16, cells: Vec<Option<char>> }
+}
+
+#[derive(Debug)]
+pub enum PatternError {
+    SizeMismatch { pattern: (i16, i16), board: (i16, i16) }
+}
+
+impl Pattern {
+    /// The color of the space at x,y on a width x height board. None if
+    /// the pattern leaves it alone
+    pub fn color_at(&self, x: i16, y: i16, width: i16, height: i16) -> Option<char> {
+        let index = match self {
+            Pattern::Checkerboard { band } => (x / band + y / band) as usize % 2,
+            Pattern::Rows { band, colors } => (y / band) as usize % colors,
+            Pattern::Columns { band, colors } => (x / band) as usize % colors,
+            Pattern::Rings { band, colors } => (x.min(y).min(width-x-1).min(height-y-1) / band) as usize % colors,
+            Pattern::Bitmap { width: bitmap_width, cells, .. } => return cells[(x + y * bitmap_width) as usize]
+        };
+
+        std::char::from_digit(index as u32, 16)
+    }
+
+    /// Bitmaps only fit boards of their own size. Everything else fits
+    /// anything
+    pub fn check_size(&self, board_width: i16, board_height: i16) -> Result<(), PatternError> {
+        match self {
+            Pattern::Bitmap { width, height, .. } if (*width, *height) != (board_width, board_height) =>
+                Err(PatternError::SizeMismatch { pattern: (*width, *height), board: (board_width, board_height) }),
+            _ => Ok(())
+        }
+    }
+
+    /// Colors the board space by space
+    pub fn apply(&self, board: &mut ColorableBoard) -> Result<(), PatternError> {
+        self.check_size(board.get_width(), board.get_height())?;
+
+        for y in 0..board.get_height() {
+            for x in 0..board.get_width() {
+                if let Some(color) = self.color_at(x, y, board.get_width(), board.get_height()) {
+                    board.set_color(x, y, color);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn load_bitmap(path: &str) -> io::Result<Pattern> {
+    bitmap_from_string(&fs::read_to_string(path)?)
+}
+
+pub fn bitmap_from_string(contents: &str) -> io::Result<Pattern> {
+    fn bad_data(msg: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+
+    let rows: Vec<&str> = contents.lines().map(|l| l.trim_end()).filter(|l| !l.is_empty()).collect();
+
+    let width = rows.first().map_or(0, |r| r.chars().count());
+    if width == 0 {
+        return Err(bad_data("Bitmap is empty".to_string()));
+    }
+
+    let mut cells = Vec::new();
+
+    for (y, row) in rows.iter().enumerate() {
+        if row.chars().count() != width {
+            return Err(bad_data(format!("Row {} is not {} spaces wide", y, width)));
+        }
+
+        for c in row.chars() {
+            match c {
+                '.' => cells.push(None),
+                c if DISPLAY_COLORS.contains(c) => cells.push(Some(c)),
+                _ => return Err(bad_data(format!("Bad color '{}' in row {}", c, y)))
+            }
+        }
+    }
+
+    Ok(Pattern::Bitmap { width: width as i16, height: rows.len() as i16, cells })
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::SizeMismatch { pattern, board } =>
+                write!(f, "The pattern is {}x{} but the board is {}x{}", pattern.0, pattern.1, board.0, board.1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colorableboard::ColorableBoard;
+    use crate::patterns::*;
+
+    fn build_board() -> ColorableBoard {
+        ColorableBoard::from_cells(4, 3, &(0..12).map(|i| Some((i / 4, '0'))).collect())
+    }
+
+    #[test]
+    fn rings() {
+        let mut b = build_board();
+        Pattern::Rings { band: 1, colors: 2 }.apply(&mut b).unwrap();
+
+        assert_eq!(b.get_color(0, 0), Some('0'));
+        assert_eq!(b.get_color(1, 1), Some('1'));
+        assert_eq!(b.get_color(2, 1), Some('1'));
+        assert_eq!(b.get_color(3, 2), Some('0'));
+    }
+
+    #[test]
+    fn stripes() {
+        let mut b = build_board();
+        Pattern::Columns { band: 2, colors: 2 }.apply(&mut b).unwrap();
+
+        assert_eq!(b.get_color(1, 2), Some('0'));
+        assert_eq!(b.get_color(2, 0), Some('1'));
+    }
+
+    #[test]
+    fn bitmap() {
+        let pattern = bitmap_from_string("0011\n0.11\n2222\n").unwrap();
+        let mut b = build_board();
+        b.set_color(1, 1, '3');
+
+        pattern.apply(&mut b).unwrap();
+
+        assert_eq!(b.get_color(3, 0), Some('1'));
+        assert_eq!(b.get_color(1, 1), Some('3'));
+        assert_eq!(b.get_color(0, 2), Some('2'));
+    }
+
+    #[test]
+    fn bad_bitmaps() {
+        assert!(bitmap_from_string("001\n01\n").is_err());
+        assert!(bitmap_from_string("0x1\n").is_err());
+        assert!(bitmap_from_string("016\n").is_err());
+        assert!(bitmap_from_string("01a\n").is_err());
+
+        let pattern = bitmap_from_string("00\n11\n").unwrap();
+        assert!(matches!(pattern.apply(&mut build_board()), Err(PatternError::SizeMismatch { .. })));
+    }
+}
@@ -0,0 +1,500 @@
+use std::fmt;
+use std::io;
+
+use polyomino::board::Board;
+use polyomino::polyomino::Polyomino;
+use polyomino::solver::Solver;
+use polyomino::utils as poly_utils;
+use polyomino::utils::PredefinedPolyominoes;
+
+use crate::alternatives::find_alternative_answers;
+use crate::alternatives::AlternativeAnswer;
+use crate::boardcolorer::color_board_pinned;
+use crate::boardcolorer::ColoringOptions;
+use crate::boardcolorer::PinError;
+use crate::colorableboard::ColorableBoard;
+use crate::coloringstats::coloring_stats;
+use crate::coloringstats::ColoringStats;
+use crate::coloringstats::StatsFilter;
+use crate::colorpolyomino::ColorPolyomino;
+use crate::metrics::difficulty_metrics;
+use crate::metrics::DifficultyMetrics;
+use crate::patterns::Pattern;
+use crate::patterns::PatternError;
+use crate::utils::*;
+
+// This was originally going to be much more complicated
+//
+// The idea is to get a set of pentomioes that are colored and form
+// them into a rectange such that the colors themselves form a
+// complete set of pentominoes.
+//
+// Take a solution
+// Four color it (typically this can be done with three colors)
+// Overlay that solution on a different solution
+// That different solution now has a set of colored pentominoes
+// Use those pentominoes and see how many solutions there are such
+//   that there are a complete set of pentominoes in the colors
+//
+// The original idea was to do a search. Take every solution and apply
+// it to every other solution (cull out uninteresting cases where you
+// end up with a mono-colored pentomino on the target solution).
+//
+// This turns out to be unnecssary. As long as you pick two solutions such
+// that you don't end up with a mono-colored pentomino after the overlay,
+// you'll probaby end up with just one viable solution
+//
+// The only other issue is picking a board with a nice initial coloring
+// Everything can be four-colored (obivously), but the algorithm is too
+// good and usually (?) only one polyomino needs the fourth color
+//
+// find_nice_colorings will look for a coloring that is perfectly balanced
+// (a surprising number are) and then you are off to the races 
+
+/// The pentominoes, each with its own id
+pub fn get_pentominoes() -> io::Result<Vec<ColorPolyomino>> {
+    let mut polyominoes = poly_utils::get_polyominoes::<ColorPolyomino>(PredefinedPolyominoes::Pentominoes)?;
+
+    polyominoes.iter_mut().enumerate().for_each(|(id, p)| p.set_id(id));
+
+    Ok(polyominoes)
+}
+
+/// How many spaces the polyominoes cover. Only boards with exactly this
+/// area can be solved
+pub fn polyomino_area(polyominoes: &Vec<ColorPolyomino>) -> usize {
+    polyominoes.iter().map(|p| p.iter().count()).sum()
+}
+
+/// Every way of tiling a width x height board with the polyominoes
+pub fn generate_solutions(width: i16, height: i16, all_polyominoes: &Vec<Vec<ColorPolyomino>>) -> Vec<ColorableBoard> {
+    let mut b = Board::new(width, height);
+
+    let mut solver = Solver::new(&mut b, all_polyominoes);
+
+    solver.solve().iter().map(|s| ColorableBoard::new(s)).collect()
+}
+
+/// A solution whose coloring is well balanced
+#[derive(Clone, Debug, PartialEq)]
+pub struct NiceColoring {
+    pub solution: usize,            // Index into the list of solutions
+    pub color_counts: Vec<i32>,     // Number of spaces of each color
+    pub stats: ColoringStats
+}
+
+impl NiceColoring {
+    pub fn colors(&self) -> usize {
+        self.color_counts.len()
+    }
+}
+
+/// What we found out about a colored board
+#[derive(Clone, Debug)]
+pub struct Verification {
+    pub tilings: usize,                         // Ways of tiling the board with the colored pieces
+    pub answers: Vec<ColorableBoard>,           // Tilings with every pattern in the colors
+    pub metrics: DifficultyMetrics,
+    pub alternatives: Vec<AlternativeAnswer>    // Answers other than the board itself
+}
+
+impl Verification {
+    pub fn is_unique(&self) -> bool {
+        self.alternatives.is_empty()
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        if self.is_unique() {
+            Outcome::Unique
+        } else {
+            Outcome::NotUnique(self.alternatives.len())
+        }
+    }
+}
+
+/// How a pair turned out. Everything but Unique is a reason to throw the
+/// pair away
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Unique,
+    BreaksPieceRule,                // A piece is mono-colored or otherwise breaks the rule
+    IdenticalPieces(usize, usize),  // Ids of two pieces colored the same
+    MissingShapes(usize),           // Number of shapes the colors do show
+    NotUnique(usize),               // Number of alternative answers
+    OverBudget(usize)               // Number of tilings, more than we were willing to check
+}
+
+/// The result of imposing the colors of a base solution on a target
+#[derive(Clone, Debug)]
+pub struct PairResult {
+    pub target: ColorableBoard,                 // The target with the base's colors on it
+    pub orientation: Orientation,
+    pub outcome: Outcome,
+    pub verification: Option<Verification>      // Missing if the target was rejected before solving
+}
+
+/// How many pairs of a search turned out each way
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutcomeHistogram {
+    pub unique: usize,
+    pub breaks_piece_rule: usize,
+    pub identical_pieces: usize,
+    pub missing_shapes: usize,
+    pub not_unique: usize,
+    pub over_budget: usize
+}
+
+/// Looks for solutions whose coloring is well balanced and gets past the
+/// filter. Solutions that can't be colored with the pins are skipped
+pub fn find_nice_colorings(solutions: &Vec<ColorableBoard>, coloring: &ColoringOptions, balance: Balance, filter: &StatsFilter) -> Vec<NiceColoring> {
+    let mut nice = Vec::new();
+
+    for (i, soln) in solutions.iter().enumerate() {
+        let mut base_solution = soln.clone();
+        if color_board_pinned(&mut base_solution, coloring).is_err() {
+            continue;
+        }
+        
+        if balance.is_balanced(&base_solution) {
+            let stats = coloring_stats(&base_solution);
+
+            if filter.allows(&stats) {
+                nice.push(NiceColoring { solution: i, color_counts: color_count(&base_solution), stats });
+            }
+        }
+    }
+
+    nice
+}
+
+/// One orientation from each class of orientations that give the same
+/// puzzle for this pair. Turning the base by a symmetry of its coloring
+/// only renames the colors, and turning the target by a symmetry of its
+/// tiling just turns the whole puzzle, so every orientation reached from
+/// another by those is equivalent. Swapping the base and target is a
+/// different puzzle (the pieces come from the target), so pairs are
+/// still ordered
+pub fn orientation_classes(colored_base: &ColorableBoard, target: &ColorableBoard) -> Vec<Orientation> {
+    let mut equivalences = colored_base.coloring_symmetries();
+    equivalences.extend(target.symmetries());
+
+    // Close it up into a group
+    let mut grown = true;
+    while grown {
+        grown = false;
+        for a in equivalences.clone() {
+            for b in equivalences.clone() {
+                if !equivalences.contains(&a.then(b)) {
+                    equivalences.push(a.then(b));
+                    grown = true;
+                }
+            }
+        }
+    }
+
+    let mut representatives: Vec<Orientation> = Vec::new();
+
+    for o in Orientation::all() {
+        if !representatives.iter().any(|r| equivalences.iter().any(|e| r.then(*e) == o)) {
+            representatives.push(o);
+        }
+    }
+
+    representatives
+}
+
+/// Colors the base and imposes it on every target in one orientation of
+/// each class (see orientation_classes). Returns the index of the target
+/// with each result
+pub fn build_all_solution_variations<P: Polyomino>(base_solution: &ColorableBoard, all_solutions: &Vec<ColorableBoard>, all_polyominoes: &Vec<Vec<P>>, coloring: &ColoringOptions, piece_rule: &PieceColorRule, max_tilings: Option<usize>) -> Result<Vec<(usize, PairResult)>, PinError> {
+    let mut colored_solution: ColorableBoard = base_solution.clone();
+            
+    color_board_pinned(&mut colored_solution, coloring)?;
+
+    let mut results = Vec::new();
+    
+    for (i, target_soln) in all_solutions.iter().enumerate() {
+        for orientation in orientation_classes(&colored_solution, target_soln) {
+            results.push((i, build_single_solution_variations(&colored_solution, target_soln, orientation, all_polyominoes, piece_rule, max_tilings)));
+        }
+    }
+
+    Ok(results)
+}
+
+pub fn build_single_solution_variations<P: Polyomino>(base_solution: &ColorableBoard, target_solution: &ColorableBoard, orientation: Orientation, all_polyominoes: &Vec<Vec<P>>, piece_rule: &PieceColorRule, max_tilings: Option<usize>) -> PairResult {
+    let mut target = target_solution.clone();
+
+    overlay(base_solution, &mut target, orientation);
+
+    match verify_colored_target(&target, all_polyominoes, piece_rule, max_tilings) {
+        Ok(verification) => PairResult { target, orientation, outcome: verification.outcome(), verification: Some(verification) },
+        Err(outcome) => PairResult { target, orientation, outcome, verification: None }
+    }
+}
+
+/// Like build_single_solution_variations, but the colors come from a
+/// pattern laid over the target space by space instead of from a colored
+/// base
+pub fn build_pattern_variation<P: Polyomino>(pattern: &Pattern, target_solution: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>, piece_rule: &PieceColorRule, max_tilings: Option<usize>) -> Result<PairResult, PatternError> {
+    let mut target = target_solution.clone();
+
+    pattern.apply(&mut target)?;
+
+    let orientation = Orientation::Normal;
+
+    Ok(match verify_colored_target(&target, all_polyominoes, piece_rule, max_tilings) {
+        Ok(verification) => PairResult { target, orientation, outcome: verification.outcome(), verification: Some(verification) },
+        Err(outcome) => PairResult { target, orientation, outcome, verification: None }
+    })
+}
+
+/// Checks that a colored board makes a good puzzle: every piece follows
+/// the rule, and the colored pieces can only be put together one way
+/// with every pattern in the colors. Boards that are rejected before
+/// they are solved come back as the reason they were rejected
+pub fn verify_colored_target<P: Polyomino>(target_solution: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>, piece_rule: &PieceColorRule, max_tilings: Option<usize>) -> Result<Verification, Outcome> {
+    // After imposing a coloring on another board, if any of the
+    // polyominoes on that board is mono-colored (or otherwise breaks
+    // the piece rule), we reject the solution as being boring
+    if !piece_rule.allows_all(target_solution) {
+        return Err(Outcome::BreaksPieceRule);
+    }
+
+    // Two pieces that look the same can always be swapped, so there
+    // is no telling which answer was intended
+    if let Some((first, second)) = find_identical_pieces(target_solution) {
+        return Err(Outcome::IdenticalPieces(first, second));
+    }
+
+    // Colors that didn't come from a tiling (see patterns) may not show
+    // every shape, and then no arrangement of the pieces is an answer
+    let shapes = count_polyomino_patterns(target_solution, all_polyominoes);
+    if shapes < all_polyominoes.len() {
+        return Err(Outcome::MissingShapes(shapes));
+    }
+
+    // Build solutions with the polyominoes that make up this solution,
+    // giving up as soon as there are more than we are willing to check.
+    // It's the same search with or without a budget, so the tilings come
+    // in the same order and the metrics don't depend on the budget
+    let limit = max_tilings.map_or(usize::MAX, |max| max.saturating_add(1));
+    let solutions = find_colored_tilings_up_to(target_solution, limit);
+
+    if let Some(max) = max_tilings {
+        if solutions.len() > max {
+            return Err(Outcome::OverBudget(solutions.len()));
+        }
+    }
+
+    // Now check to see if any of those solutions has a connected
+    // blob of color in the pattern of every polyomino
+    let valid_solution_variations: Vec<&ColorableBoard> = solutions.iter()
+        .filter(|s| has_all_polyomino_patterns(s, all_polyominoes))
+        .collect();
+
+    // The target itself is always an answer, so anything else is
+    // a problem worth looking at
+    let alternatives = find_alternative_answers(target_solution, &valid_solution_variations, all_polyominoes);
+
+    Ok(Verification {
+        tilings: solutions.len(),
+        answers: valid_solution_variations.into_iter().cloned().collect(),
+        metrics: difficulty_metrics(&solutions, all_polyominoes),
+        alternatives
+    })
+}
+
+impl OutcomeHistogram {
+    pub fn add(&mut self, outcome: &Outcome) {
+        match outcome {
+            Outcome::Unique => self.unique += 1,
+            Outcome::BreaksPieceRule => self.breaks_piece_rule += 1,
+            Outcome::IdenticalPieces(_, _) => self.identical_pieces += 1,
+            Outcome::MissingShapes(_) => self.missing_shapes += 1,
+            Outcome::NotUnique(_) => self.not_unique += 1,
+            Outcome::OverBudget(_) => self.over_budget += 1
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.unique + self.breaks_piece_rule + self.identical_pieces + self.missing_shapes + self.not_unique + self.over_budget
+    }
+}
+
+pub fn outcome_histogram(results: &Vec<(usize, PairResult)>) -> OutcomeHistogram {
+    let mut histogram = OutcomeHistogram::default();
+
+    results.iter().for_each(|(_target, result)| histogram.add(&result.outcome));
+
+    histogram
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Unique => write!(f, "Unique answer"),
+            Outcome::BreaksPieceRule => write!(f, "Target has a piece that breaks the piece color rule"),
+            Outcome::IdenticalPieces(first, second) => write!(f, "Pieces #{} and #{} are colored the same", first, second),
+            Outcome::MissingShapes(shapes) => write!(f, "The colors only show {} of the shapes", shapes),
+            Outcome::NotUnique(alternatives) => write!(f, "{} alternative answer(s)", alternatives),
+            Outcome::OverBudget(tilings) => write!(f, "Gave up after finding {} tilings", tilings)
+        }
+    }
+}
+
+impl fmt::Display for OutcomeHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.total().max(1) as f64;
+
+        for (label, count) in [("Unique answer", self.unique),
+                               ("Piece breaks the rule", self.breaks_piece_rule),
+                               ("Identical pieces", self.identical_pieces),
+                               ("Missing shapes", self.missing_shapes),
+                               ("Not unique", self.not_unique),
+                               ("Over budget", self.over_budget)] {
+            writeln!(f, "{:>22}: {:>6} {}", label, count, "#".repeat((50.0 * count as f64 / total).round() as usize))?;
+        }
+
+        write!(f, "{:>22}: {:>6}", "Total", self.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polyomino::point::Point;
+    use polyomino::point::SimplePoint;
+    use polyomino::polyomino::SimplePolyomino;
+    use polyomino::utils as poly_utils;
+    use polyomino::utils::Restrictions;
+
+    use crate::boardcolorer::color_board;
+    use crate::pipeline::*;
+
+    #[test]
+    fn nice_colorings_are_balanced() {
+        let polyominoes = get_pentominoes().unwrap();
+        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+
+        let solutions = generate_solutions(15, 4, &all_polyominoes);
+        let nice = find_nice_colorings(&solutions, &ColoringOptions::default(), Balance::Exact, &StatsFilter::default());
+
+        assert!(!nice.is_empty());
+
+        for coloring in &nice {
+            assert!(coloring.solution < solutions.len());
+            assert_eq!(coloring.color_counts.iter().sum::<i32>(), 60);
+            assert!(coloring.color_counts.iter().all(|c| *c == 60 / coloring.colors() as i32));
+        }
+
+        // Loosening the balance can only find more
+        assert!(find_nice_colorings(&solutions, &ColoringOptions::default(), Balance::Within(5), &StatsFilter::default()).len() >= nice.len());
+
+        // Base colorings never have two pieces of one color side by side,
+        // so only corners can touch. Ruling out corners keeps exactly the
+        // nice colorings without any, and some have them
+        let strict = StatsFilter { max_diagonal_contacts: Some(0), ..Default::default() };
+        let kept: Vec<usize> = find_nice_colorings(&solutions, &ColoringOptions::default(), Balance::Exact, &strict).iter().map(|n| n.solution).collect();
+        let expected: Vec<usize> = nice.iter().filter(|n| n.stats.diagonal_contacts == 0).map(|n| n.solution).collect();
+
+        assert_eq!(kept, expected);
+        assert!(kept.len() < nice.len());
+    }
+
+    #[test]
+    fn symmetric_pairs() {
+        // +-+-+
+        // |a|b|
+        // + + +
+        // |b|a|
+        // +-+-+
+        let checkered = ColorableBoard::from_cells(2, 2, &vec![Some((0, 'a')), Some((1, 'b')), Some((0, 'b')), Some((1, 'a'))]);
+        let lopsided = ColorableBoard::from_cells(2, 2, &vec![Some((0, 'a')), Some((1, 'a')), Some((2, 'a')), Some((3, 'b'))]);
+
+        assert_eq!(orientation_classes(&checkered, &lopsided), vec![Orientation::Normal]);
+        assert_eq!(orientation_classes(&lopsided, &checkered), vec![Orientation::Normal, Orientation::OneEighty]);
+        assert_eq!(orientation_classes(&lopsided, &lopsided), Orientation::all());
+    }
+
+    #[test]
+    fn patterns_must_show_every_shape() {
+        let polyominoes = get_pentominoes().unwrap();
+        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+        let solutions = generate_solutions(15, 4, &all_polyominoes);
+
+        // Every piece gets both colors, but no two spaces of one color
+        // are side by side, so there are no shapes at all
+        let result = build_pattern_variation(&Pattern::Checkerboard { band: 1 }, &solutions[0], &all_polyominoes, &PieceColorRule::default(), None).unwrap();
+
+        assert_eq!(result.outcome, Outcome::MissingShapes(0));
+        assert!(result.verification.is_none());
+    }
+
+    #[test]
+    fn budget_stops_early() {
+        let polyominoes = get_pentominoes().unwrap();
+        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+        let solutions = generate_solutions(15, 4, &all_polyominoes);
+
+        let mut base = solutions[0].clone();
+        color_board(&mut base);
+
+        // Every target can be tiled at least four ways (turn the whole
+        // board over), so a budget of two stops at the third
+        let over_budget = solutions.iter().skip(1).find_map(|s| {
+            let mut target = s.clone();
+            overlay(&base, &mut target, Orientation::Normal);
+
+            match verify_colored_target(&target, &all_polyominoes, &PieceColorRule::default(), Some(2)) {
+                Err(Outcome::OverBudget(tilings)) => Some(tilings),
+                _ => None
+            }
+        });
+
+        assert_eq!(over_budget, Some(3));
+    }
+
+    // +-+-+-+-+-+-+
+    // |1 0|1|1 1|2|
+    // + +-+ + +-+ +
+    // |0|0 2|0|2 1|
+    // +-+-+-+-+-+-+
+    //
+    // Four L trominoes, colored to show an I and an L
+    #[test]
+    fn budget_keeps_metrics() {
+        let shapes = vec![SimplePolyomino::new(vec![SimplePoint::new(0, 0), SimplePoint::new(1, 0), SimplePoint::new(2, 0)]),
+                          SimplePolyomino::new(vec![SimplePoint::new(0, 0), SimplePoint::new(1, 0), SimplePoint::new(0, 1)])];
+        let all_polyominoes = poly_utils::build_variations(&shapes, Restrictions::None);
+
+        let ids = [0, 0, 1, 2, 2, 3,
+                   0, 1, 1, 2, 3, 3];
+        let target = ColorableBoard::from_cells(6, 2, &ids.iter().zip("101112002221".chars()).map(|(id, color)| Some((*id, color))).collect());
+
+        let unbudgeted = verify_colored_target(&target, &all_polyominoes, &PieceColorRule::default(), None).unwrap();
+        assert!(unbudgeted.metrics.explored_before_answer.unwrap() > 0);
+
+        // A budget with room for every tiling finds them in the same order
+        let budgeted = verify_colored_target(&target, &all_polyominoes, &PieceColorRule::default(), Some(unbudgeted.tilings)).unwrap();
+
+        assert_eq!(budgeted.tilings, unbudgeted.tilings);
+        assert_eq!(budgeted.metrics, unbudgeted.metrics);
+
+        assert!(matches!(verify_colored_target(&target, &all_polyominoes, &PieceColorRule::default(), Some(unbudgeted.tilings - 1)),
+                         Err(Outcome::OverBudget(tilings)) if tilings == unbudgeted.tilings));
+    }
+
+    #[test]
+    fn histogram() {
+        let mut histogram = OutcomeHistogram::default();
+
+        histogram.add(&Outcome::Unique);
+        histogram.add(&Outcome::NotUnique(2));
+        histogram.add(&Outcome::NotUnique(5));
+        histogram.add(&Outcome::BreaksPieceRule);
+
+        assert_eq!(histogram.not_unique, 2);
+        assert_eq!(histogram.over_budget, 0);
+        assert_eq!(histogram.total(), 4);
+    }
+}
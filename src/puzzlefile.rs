@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorableboard::IndexedBoardState;
+
+// A puzzle file is a plain text picture of a colored board. The
+// "pieces" section gives the id of the polyomino on each space (as a
+// base 36 digit, so at most 36 polyominoes) and the "colors" section
+// gives its color. Spaces without a polyomino are '.'. Boards with ids
+// past 'z' are saved with their pieces numbered again from 0
+//
+// size 5 2
+// pieces
+// 00011
+// 02221
+// colors
+// 01122
+// 00011
+
+pub fn save_puzzle(board: &ColorableBoard, path: &str) -> io::Result<()> {
+    fs::write(path, puzzle_to_string(board)?)
+}
+
+pub fn load_puzzle(path: &str) -> io::Result<ColorableBoard> {
+    puzzle_from_string(&fs::read_to_string(path)?)
+}
+
+pub fn puzzle_to_string(board: &ColorableBoard) -> io::Result<String> {
+    let ids = piece_ids(board)?;
+    let mut pieces = String::new();
+    let mut colors = String::new();
+
+    for y in 0..board.get_height() {
+        for x in 0..board.get_width() {
+            if let IndexedBoardState::Full(p_idx, _pt, _x, _y) = board.get(x, y) {
+                pieces.push(ids[&p_idx]);
+            } else {
+                pieces.push('.');
+            }
+            colors.push(board.get_color(x, y).unwrap_or('.'));
+        }
+        pieces.push('\n');
+        colors.push('\n');
+    }
+
+    Ok(format!("size {} {}\npieces\n{}colors\n{}", board.get_width(), board.get_height(), pieces, colors))
+}
+
+// The digit for each piece on the board. The pieces keep their own ids
+// when they all fit, otherwise they're numbered in the order they're met
+fn piece_ids(board: &ColorableBoard) -> io::Result<HashMap<usize, char>> {
+    let mut order = Vec::new();
+
+    for y in 0..board.get_height() {
+        for x in 0..board.get_width() {
+            if let IndexedBoardState::Full(p_idx, _pt, _x, _y) = board.get(x, y) {
+                if !order.contains(&p_idx) {
+                    order.push(p_idx);
+                }
+            }
+        }
+    }
+
+    if order.len() > 36 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("A puzzle file holds at most 36 pieces, not {}", order.len())));
+    }
+
+    let own = order.iter().map(|p_idx| std::char::from_digit(board.get_polyomino(*p_idx).get_id() as u32, 36)).collect::<Option<Vec<char>>>();
+
+    Ok(match own {
+        Some(digits) => order.into_iter().zip(digits).collect(),
+        None => order.into_iter().enumerate().map(|(n, p_idx)| (p_idx, std::char::from_digit(n as u32, 36).unwrap())).collect()
+    })
+}
+
+pub fn puzzle_from_string(contents: &str) -> io::Result<ColorableBoard> {
+    fn bad_data(msg: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+
+    let lines: Vec<&str> = contents.lines().map(|l| l.trim_end()).filter(|l| !l.is_empty()).collect();
+
+    let size: Vec<&str> = lines.first().map(|l| l.split_whitespace().collect()).unwrap_or_default();
+    if size.len() != 3 || size[0] != "size" {
+        return Err(bad_data("Puzzle file must start with 'size <width> <height>'".to_string()));
+    }
+
+    let width = size[1].parse::<i16>().map_err(|e| bad_data(format!("Bad width '{}': {}", size[1], e)))?;
+    let height = size[2].parse::<i16>().map_err(|e| bad_data(format!("Bad height '{}': {}", size[2], e)))?;
+    if width <= 0 || height <= 0 {
+        return Err(bad_data(format!("Bad size {}x{}", width, height)));
+    }
+
+    let rows = height as usize;
+
+    if lines.len() != 3 + 2 * rows || lines[1] != "pieces" || lines[2 + rows] != "colors" {
+        return Err(bad_data(format!("Expected a 'pieces' and a 'colors' section of {} rows each", rows)));
+    }
+
+    let piece_rows = &lines[2..2 + rows];
+    let color_rows = &lines[3 + rows..];
+
+    let mut cells = Vec::new();
+
+    for (y, (piece_row, color_row)) in piece_rows.iter().zip(color_rows.iter()).enumerate() {
+        if piece_row.chars().count() != width as usize || color_row.chars().count() != width as usize {
+            return Err(bad_data(format!("Row {} is not {} spaces wide", y, width)));
+        }
+
+        for (piece, color) in piece_row.chars().zip(color_row.chars()) {
+            cells.push(match (piece, color) {
+                ('.', _) => None,
+                (_, '.') => return Err(bad_data(format!("Missing color in row {}", y))),
+                _ => match piece.to_digit(36) {
+                    Some(id) => Some((id as usize, color)),
+                    None => return Err(bad_data(format!("Bad piece id '{}' in row {}", piece, y)))
+                }
+            });
+        }
+    }
+
+    Ok(ColorableBoard::from_cells(width, height, &cells))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzlefile::*;
+
+    const PUZZLE: &str = "size 5 2\npieces\n00011\n02221\ncolors\n01122\n00011\n";
+
+    #[test]
+    fn round_trip() {
+        let board = puzzle_from_string(PUZZLE).unwrap();
+
+        assert_eq!(board.get_color(4, 0), Some('2'));
+        assert!(board.get(0, 0).get_poly_idx() == board.get(0, 1).get_poly_idx());
+        assert!(board.get(0, 1).get_poly_idx() != board.get(1, 1).get_poly_idx());
+        assert_eq!(puzzle_to_string(&board).unwrap(), PUZZLE);
+    }
+
+    #[test]
+    fn wrong_size() {
+        assert!(puzzle_from_string("size 4 2\npieces\n00011\n02221\ncolors\n01122\n00011\n").is_err());
+    }
+
+    #[test]
+    fn large_ids() {
+        // +-+-+-+
+        // |a a|b|
+        // +-+-+ +
+        // |c|b b|
+        // +-+-+-+
+        let ids = vec![40, 40, 7, 50, 7, 7];
+        let board = ColorableBoard::from_cells(3, 2, &ids.iter().map(|id| Some((*id, '0'))).collect());
+
+        assert_eq!(puzzle_to_string(&board).unwrap(), "size 3 2\npieces\n001\n211\ncolors\n000\n000\n");
+
+        let ids: Vec<usize> = (0..37).collect();
+        let board = ColorableBoard::from_cells(37, 1, &ids.iter().map(|id| Some((*id, '0'))).collect());
+
+        assert!(puzzle_to_string(&board).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorableboard::IndexedBoardState;
+use crate::colorpoint::make_rgb;
+
+// PNG pictures of a puzzle, for sharing where the terminal display
+// won't do. There are three
+//
+// * pieces - the colored pieces taken apart and laid out in a row, the
+//   way the puzzle is handed to a solver
+// * tiling - the pieces put together, outlined piece by piece
+// * answer - the same, but outlined along the colors, so the hidden
+//   shapes stand out (with thin lines where pieces meet)
+//
+// Every space is cell_size pixels square. The palette overrides the
+// usual colors (see make_rgb) one color at a time
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterOptions {
+    pub cell_size: u32,
+    pub palette: BTreeMap<char, (u8, u8, u8)>
+}
+
+impl Default for RasterOptions {
+    fn default() -> RasterOptions {
+        RasterOptions { cell_size: 24, palette: BTreeMap::new() }
+    }
+}
+
+impl RasterOptions {
+    pub fn rgb(&self, color: char) -> (u8, u8, u8) {
+        *self.palette.get(&color).unwrap_or(&make_rgb(color))
+    }
+
+    // Heavy lines are about an eighth of a space wide
+    fn line_width(&self) -> u32 {
+        (self.cell_size / 8).max(1)
+    }
+}
+
+const BACKGROUND: (u8, u8, u8) = (255, 255, 255);
+const OUTLINE: (u8, u8, u8) = (0, 0, 0);
+const SEAM: (u8, u8, u8) = (96, 96, 96);
+
+/// An RGB picture
+#[derive(Clone, Debug, PartialEq)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Canvas {
+        let pixels = [BACKGROUND.0, BACKGROUND.1, BACKGROUND.2].repeat((width * height) as usize);
+        Canvas { width, height, pixels }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> (u8, u8, u8) {
+        let i = ((x + y * self.width) * 3) as usize;
+        (self.pixels[i], self.pixels[i+1], self.pixels[i+2])
+    }
+
+    /// Clipped to the canvas
+    pub fn fill_rect(&mut self, left: u32, top: u32, width: u32, height: u32, (r, g, b): (u8, u8, u8)) {
+        for y in top..(top + height).min(self.height) {
+            for x in left..(left + width).min(self.width) {
+                let i = ((x + y * self.width) * 3) as usize;
+                self.pixels[i..i+3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+// Which edges between spaces get a heavy line
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outline {
+    Pieces,
+    Colors
+}
+
+// The color of a space and the piece it is part of
+type Space = Option<(char, usize)>;
+
+// The x, y and color of each space of a piece
+type Cells = Vec<(i16, i16, char)>;
+
+pub fn pieces_image(board: &ColorableBoard, options: &RasterOptions) -> Canvas {
+    let mut pieces: Vec<(usize, Cells)> = board.piece_cells().into_iter().collect();
+    pieces.sort_by_key(|(id, _cells)| *id);
+
+    // Each piece moved to the corner of its own box, a space apart
+    let mut layout: Vec<(i16, Cells)> = Vec::new();
+    let (mut left, mut height) = (1, 0);
+
+    for (_id, cells) in pieces {
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        let moved: Cells = cells.iter().map(|(x, y, color)| (x - min_x, y - min_y, *color)).collect();
+
+        let width = moved.iter().map(|c| c.0).max().unwrap_or(0) + 1;
+        height = height.max(moved.iter().map(|c| c.1).max().unwrap_or(0) + 1);
+
+        layout.push((left, moved));
+        left += width + 1;
+    }
+
+    let (spaces_wide, spaces_high) = (left, height + 2);
+
+    let space = |x: i16, y: i16| -> Space {
+        layout.iter().enumerate()
+            .find_map(|(piece, (left, cells))| cells.iter()
+                      .find(|c| c.0 + left == x && c.1 + 1 == y)
+                      .map(|c| (c.2, piece)))
+    };
+
+    draw_spaces(spaces_wide, spaces_high, space, Outline::Pieces, options)
+}
+
+pub fn tiling_image(board: &ColorableBoard, options: &RasterOptions) -> Canvas {
+    draw_spaces(board.get_width(), board.get_height(), |x, y| board_space(board, x, y), Outline::Pieces, options)
+}
+
+pub fn answer_image(board: &ColorableBoard, options: &RasterOptions) -> Canvas {
+    draw_spaces(board.get_width(), board.get_height(), |x, y| board_space(board, x, y), Outline::Colors, options)
+}
+
+fn board_space(board: &ColorableBoard, x: i16, y: i16) -> Space {
+    match board.get(x, y) {
+        IndexedBoardState::Full(p_idx, pt_idx, _x, _y) => Some((board.get_point(p_idx, pt_idx).get_color(), p_idx)),
+        _ => None
+    }
+}
+
+fn draw_spaces<F: Fn(i16, i16) -> Space>(width: i16, height: i16, space: F, outline: Outline, options: &RasterOptions) -> Canvas {
+    let size = options.cell_size;
+    let line = options.line_width();
+
+    // Room for the outline on the right and bottom edges
+    let mut canvas = Canvas::new(width as u32 * size + line, height as u32 * size + line);
+
+    for y in 0..height {
+        for x in 0..width {
+            if let Some((color, _piece)) = space(x, y) {
+                canvas.fill_rect(x as u32 * size, y as u32 * size, size, size, options.rgb(color));
+            }
+        }
+    }
+
+    // Every edge with a space on at least one side, looking left and up
+    // from each space (and one past the end to close off the last row
+    // and column)
+    for y in 0..=height {
+        for x in 0..=width {
+            let here = if x < width && y < height { space(x, y) } else { None };
+
+            for (other, vertical) in [(if x > 0 && y < height { space(x-1, y) } else { None }, true),
+                                      (if y > 0 && x < width { space(x, y-1) } else { None }, false)] {
+                let (color, thickness) = match (here, other) {
+                    (None, None) => continue,
+                    (Some(a), Some(b)) if a == b => continue,
+                    (Some((color_a, piece_a)), Some((color_b, piece_b))) => match outline {
+                        Outline::Pieces if piece_a != piece_b => (OUTLINE, line),
+                        Outline::Colors if color_a != color_b => (OUTLINE, line),
+                        Outline::Colors if piece_a != piece_b => (SEAM, 1),
+                        _ => continue
+                    },
+                    _ => (OUTLINE, line)
+                };
+
+                let (px, py) = (x as u32 * size, y as u32 * size);
+                if vertical {
+                    canvas.fill_rect(px, py, thickness, size + line, color);
+                } else {
+                    canvas.fill_rect(px, py, size + line, thickness, color);
+                }
+            }
+        }
+    }
+
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colorableboard::ColorableBoard;
+    use crate::raster::*;
+
+    // +-+-+-+
+    // |0 1|1|
+    // +-+-+ +
+    // |1|1 1|
+    // +-+-+-+
+    fn build_board() -> ColorableBoard {
+        ColorableBoard::from_cells(3, 2, &vec![Some((5, '0')), Some((5, '1')), Some((7, '1')),
+                                               Some((6, '1')), Some((7, '1')), Some((7, '1'))])
+    }
+
+    #[test]
+    fn answer() {
+        let options = RasterOptions { cell_size: 8, ..Default::default() };
+        let canvas = answer_image(&build_board(), &options);
+
+        assert_eq!((canvas.width, canvas.height), (25, 17));
+        assert_eq!(canvas.get(4, 4), (255, 0, 0));
+        assert_eq!(canvas.get(12, 4), (0, 0, 255));
+
+        // Pieces 5 and 7 are both blue where they meet, so that's a seam,
+        // not an outline
+        assert_eq!(canvas.get(16, 4), SEAM);
+        assert_eq!(tiling_image(&build_board(), &options).get(16, 4), OUTLINE);
+    }
+
+    #[test]
+    fn palette() {
+        let mut options = RasterOptions { cell_size: 8, ..Default::default() };
+        options.palette.insert('0', (10, 20, 30));
+
+        assert_eq!(tiling_image(&build_board(), &options).get(4, 4), (10, 20, 30));
+    }
+
+    #[test]
+    fn pieces() {
+        let options = RasterOptions { cell_size: 4, ..Default::default() };
+        let canvas = pieces_image(&build_board(), &options);
+
+        // Pieces 2, 1 and 2 spaces wide with a space around each
+        assert_eq!((canvas.width, canvas.height), (9 * 4 + 1, 4 * 4 + 1));
+        assert_eq!(canvas.get(0, 0), (255, 255, 255));
+
+        let mut png = Vec::new();
+        canvas.write_png(&mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
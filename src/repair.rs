@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+
+use polyomino::polyomino::Polyomino;
+
+use crate::alternatives::solve_for_alternative_answers;
+use crate::colorableboard::ColorableBoard;
+use crate::rng::Rng;
+use crate::utils::*;
+
+// Tries to turn a board with several answers into a board with just
+// one by changing colors. This is simulated annealing: a random change
+// is always kept if it doesn't make things worse and is sometimes kept
+// if it does, less and less often as the search goes on. A starting
+// temperature of zero makes it plain hill climbing.
+//
+// There are two kinds of change
+// * recolor a single space
+// * recolor a whole region of one color (one of the hidden shapes)
+//
+// A change is only considered if the board itself is still an answer
+// and every piece still follows the piece color rule. Most single space changes break
+// the hidden shapes, but they are cheap to reject and they are the only
+// way to change the colors within a hidden shape.
+
+pub struct RepairOptions {
+    pub iterations: usize,
+    pub start_temperature: f64,
+    pub seed: u64,
+    pub piece_rule: PieceColorRule
+}
+
+impl Default for RepairOptions {
+    fn default() -> RepairOptions {
+        RepairOptions { iterations: 200, start_temperature: 1.0, seed: 1, piece_rule: PieceColorRule::default() }
+    }
+}
+
+pub struct RepairResult {
+    pub board: ColorableBoard,
+    pub alternatives: usize,
+    pub starting_alternatives: usize,
+    pub changes_tried: usize
+}
+
+pub fn repair<P: Polyomino>(board: &ColorableBoard, all_polyominoes: &Vec<Vec<P>>, options: &RepairOptions) -> RepairResult {
+    let mut rng = Rng::new(options.seed);
+
+    let colors = board_colors(board);
+
+    let mut current = board.clone();
+    let mut current_score = solve_for_alternative_answers(&current, all_polyominoes).len();
+    let starting_alternatives = current_score;
+
+    let mut best = current.clone();
+    let mut best_score = current_score;
+
+    let mut changes_tried = 0;
+
+    while changes_tried < options.iterations && best_score > 0 && colors.len() > 1 {
+        changes_tried += 1;
+
+        let x = rng.below(board.get_width() as usize) as i16;
+        let y = rng.below(board.get_height() as usize) as i16;
+
+        let old_color = match current.get_color(x, y) {
+            Some(color) => color,
+            None => continue
+        };
+
+        let other_colors: Vec<&char> = colors.iter().filter(|c| **c != old_color).collect();
+        let new_color = *other_colors[rng.below(other_colors.len())];
+
+        let mut candidate = current.clone();
+
+        if rng.below(2) == 0 {
+            candidate.set_color(x, y, new_color);
+        } else {
+            color_region(&current, x, y).iter().for_each(|(rx, ry)| candidate.set_color(*rx, *ry, new_color));
+        }
+
+        if !options.piece_rule.allows_all(&candidate) || !has_all_polyomino_patterns(&candidate, all_polyominoes) {
+            continue;
+        }
+
+        let score = solve_for_alternative_answers(&candidate, all_polyominoes).len();
+
+        let temperature = options.start_temperature * (1.0 - changes_tried as f64 / options.iterations as f64);
+
+        let accept = score <= current_score ||
+            (temperature > 0.0 && rng.next_f64() < (-((score - current_score) as f64) / temperature).exp());
+
+        if accept {
+            current = candidate;
+            current_score = score;
+
+            if current_score < best_score {
+                best = current.clone();
+                best_score = current_score;
+            }
+        }
+    }
+
+    RepairResult { board: best, alternatives: best_score, starting_alternatives, changes_tried }
+}
+
+fn board_colors(board: &ColorableBoard) -> Vec<char> {
+    let mut colors: Vec<char> = Vec::new();
+
+    for y in 0..board.get_height() {
+        for x in 0..board.get_width() {
+            if let Some(color) = board.get_color(x, y) {
+                if !colors.contains(&color) {
+                    colors.push(color);
+                }
+            }
+        }
+    }
+
+    colors.sort();
+    colors
+}
+
+// All the spaces connected (NSEW) to x,y that have the same color
+fn color_region(board: &ColorableBoard, x: i16, y: i16) -> HashSet<(i16, i16)> {
+    let mut region = HashSet::new();
+    let mut to_visit = vec![(x, y)];
+    let color = board.get_color(x, y);
+
+    while let Some((vx, vy)) = to_visit.pop() {
+        if color.is_some() && board.get_color(vx, vy) == color && region.insert((vx, vy)) {
+            to_visit.push((vx+1, vy));
+            to_visit.push((vx-1, vy));
+            to_visit.push((vx, vy+1));
+            to_visit.push((vx, vy-1));
+        }
+    }
+
+    region
+}
+
+#[cfg(test)]
+mod tests {
+    use polyomino::point::Point;
+    use polyomino::point::SimplePoint;
+    use polyomino::polyomino::SimplePolyomino;
+    use polyomino::utils as poly_utils;
+    use polyomino::utils::Restrictions;
+
+    use crate::alternatives::solve_for_alternative_answers;
+    use crate::repair::*;
+
+    // The I and L trominoes, in every orientation
+    fn build_shapes() -> Vec<Vec<SimplePolyomino<SimplePoint>>> {
+        let shapes = vec![SimplePolyomino::new(vec![SimplePoint::new(0, 0), SimplePoint::new(1, 0), SimplePoint::new(2, 0)]),
+                          SimplePolyomino::new(vec![SimplePoint::new(0, 0), SimplePoint::new(1, 0), SimplePoint::new(0, 1)])];
+
+        poly_utils::build_variations(&shapes, Restrictions::None)
+    }
+
+    // Four L trominoes
+    //
+    // +-+-+-+-+-+-+
+    // |1 0|0|0 2|1|
+    // + +-+ + +-+ +
+    // |1|1 2|2|0 1|
+    // +-+-+-+-+-+-+
+    //
+    // colored so that there are a few ways of putting them together
+    fn build_board() -> ColorableBoard {
+        let ids = [0, 0, 1, 2, 2, 3,
+                   0, 1, 1, 2, 3, 3];
+        let colors = "100021112201";
+
+        ColorableBoard::from_cells(6, 2, &ids.iter().zip(colors.chars()).map(|(id, color)| Some((*id, color))).collect())
+    }
+
+    #[test]
+    fn repairs() {
+        let all_polyominoes = build_shapes();
+        let board = build_board();
+        let options = RepairOptions { iterations: 50, seed: 3, ..Default::default() };
+
+        let result = repair(&board, &all_polyominoes, &options);
+
+        assert!(result.starting_alternatives > 0);
+        assert!(result.alternatives <= result.starting_alternatives);
+        assert_eq!(solve_for_alternative_answers(&result.board, &all_polyominoes).len(), result.alternatives);
+
+        // Only the colors change. The intended arrangement still shows
+        // every shape and keeps to the rule
+        assert!(has_all_polyomino_patterns(&result.board, &all_polyominoes));
+        assert!(options.piece_rule.allows_all(&result.board));
+
+        let spaces = |b: &ColorableBoard| (0..b.get_height()).flat_map(|y| (0..b.get_width()).map(move |x| (x, y))).map(|(x, y)| b.get(x, y).get_poly_idx()).collect::<Vec<_>>();
+        assert_eq!(spaces(&result.board), spaces(&board));
+
+        // The same seed finds the same coloring
+        let again = repair(&board, &all_polyominoes, &options);
+        assert_eq!(again.board.piece_cells(), result.board.piece_cells());
+        assert_eq!(again.changes_tried, result.changes_tried);
+    }
+
+    #[test]
+    fn unique_boards_are_left_alone() {
+        let all_polyominoes = build_shapes();
+        let board = build_board();
+        let repaired = repair(&board, &all_polyominoes, &RepairOptions { iterations: 50, seed: 3, ..Default::default() });
+
+        assert_eq!(repaired.alternatives, 0);
+
+        let result = repair(&repaired.board, &all_polyominoes, &RepairOptions { start_temperature: 0.0, ..Default::default() });
+
+        assert_eq!(result.starting_alternatives, 0);
+        assert_eq!(result.changes_tried, 0);
+        assert_eq!(result.board.piece_cells(), repaired.board.piece_cells());
+    }
+}
@@ -0,0 +1,66 @@
+// A small, seedable random number generator (xorshift64*). We want
+// searches to be repeatable from a seed on any machine and with any
+// version of the code, which is easiest to guarantee by not depending
+// on anybody else's generator
+
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Zero is a fixed point of xorshift, so scramble the seed first
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        state ^= state >> 31;
+
+        Rng { state: if state == 0 { 1 } else { state } }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut r1 = Rng::new(42);
+        let mut r2 = Rng::new(42);
+
+        assert!((0..100).all(|_| r1.next_u64() == r2.next_u64()));
+    }
+
+    #[test]
+    fn zero_seed() {
+        let mut r = Rng::new(0);
+
+        assert!(r.next_u64() != r.next_u64());
+    }
+
+    #[test]
+    fn in_range() {
+        let mut r = Rng::new(7);
+
+        assert!((0..1000).all(|_| r.below(5) < 5));
+        assert!((0..1000).map(|_| r.next_f64()).all(|f| (0.0..1.0).contains(&f)));
+    }
+}
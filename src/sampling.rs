@@ -0,0 +1,183 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use polyomino::polyomino::Polyomino;
+
+use crate::adjacency::Adjacency;
+use crate::boardcolorer::color_board_randomly;
+use crate::colorableboard::ColorableBoard;
+use crate::pipeline::*;
+use crate::rng::Rng;
+use crate::utils::*;
+
+// For boards with too many solutions to try every pair, try random
+// pairs instead. Each sample gets its own seed, drawn from the seed of
+// the whole run, and everything about the sample (base, coloring, target
+// and orientation) comes from that one seed. So a hit can be reproduced
+// from its seed alone, without rerunning the samples before it, as long
+// as the solutions are generated in the same order.
+
+pub struct SampleOptions {
+    pub samples: Option<usize>,         // Stop after this many samples
+    pub time_budget: Option<Duration>,  // Stop after this long
+    pub seed: u64,
+    pub adjacency: Adjacency,           // For coloring the bases
+    pub piece_rule: PieceColorRule,
+    pub max_tilings: Option<usize>
+}
+
+impl Default for SampleOptions {
+    fn default() -> SampleOptions {
+        SampleOptions { samples: Some(100), time_budget: None, seed: 1, adjacency: Adjacency::default(), piece_rule: PieceColorRule::default(), max_tilings: None }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub index: usize,       // Which sample of the run this was
+    pub seed: u64,          // The seed that reproduces it
+    pub base: usize,
+    pub target: usize,
+    pub result: PairResult
+}
+
+pub struct SamplingResult {
+    pub hits: Vec<Sample>,  // Samples with a unique answer
+    pub samples: usize,
+    pub histogram: OutcomeHistogram
+}
+
+pub fn sample_pairs<P: Polyomino>(solutions: &Vec<ColorableBoard>, all_polyominoes: &Vec<Vec<P>>, options: &SampleOptions) -> SamplingResult {
+    let mut rng = Rng::new(options.seed);
+    let start = Instant::now();
+
+    let mut hits = Vec::new();
+    let mut histogram = OutcomeHistogram::default();
+    let mut samples = 0;
+
+    while !solutions.is_empty() &&
+        options.samples.is_none_or(|n| samples < n) &&
+        options.time_budget.is_none_or(|t| start.elapsed() < t) {
+        let mut sample = draw_sample(solutions, rng.next_u64(), all_polyominoes, options.adjacency, &options.piece_rule, options.max_tilings);
+        sample.index = samples;
+        samples += 1;
+
+        histogram.add(&sample.result.outcome);
+
+        if sample.result.outcome == Outcome::Unique {
+            hits.push(sample);
+        }
+    }
+
+    SamplingResult { hits, samples, histogram }
+}
+
+/// Everything random about a sample comes from its seed
+pub fn draw_sample<P: Polyomino>(solutions: &Vec<ColorableBoard>, seed: u64, all_polyominoes: &Vec<Vec<P>>, adjacency: Adjacency, piece_rule: &PieceColorRule, max_tilings: Option<usize>) -> Sample {
+    let mut rng = Rng::new(seed);
+
+    let base = rng.below(solutions.len());
+
+    // A board imposed on itself is never interesting, so pick a
+    // different target if there is one
+    let mut target = rng.below(solutions.len());
+    if solutions.len() > 1 {
+        while target == base {
+            target = rng.below(solutions.len());
+        }
+    }
+
+    let orientation = Orientation::all()[rng.below(4)];
+
+    let mut base_solution = solutions[base].clone();
+    color_board_randomly(&mut base_solution, adjacency, &mut rng);
+
+    let result = build_single_solution_variations(&base_solution, &solutions[target], orientation, all_polyominoes, piece_rule, max_tilings);
+
+    Sample { index: 0, seed, base, target, result }
+}
+
+#[cfg(test)]
+mod tests {
+    use polyomino::utils as poly_utils;
+    use polyomino::utils::Restrictions;
+
+    use crate::boardcolorer::color_board_randomly;
+    use crate::colorableboard::IndexedBoardState;
+    use crate::rng::Rng;
+    use crate::sampling::*;
+
+    #[test]
+    fn random_colorings_are_proper() {
+        let polyominoes = get_pentominoes().unwrap();
+        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+        let solutions = generate_solutions(15, 4, &all_polyominoes);
+
+        let mut rng = Rng::new(3);
+
+        for soln in solutions.iter().take(10) {
+            let mut b = soln.clone();
+            color_board_randomly(&mut b, Adjacency::Edge, &mut rng);
+
+            for y in 0..b.get_height() {
+                for x in 0..b.get_width() {
+                    for (nx, ny) in [(x+1, y), (x, y+1)] {
+                        if let (IndexedBoardState::Full(p1, _, _, _), IndexedBoardState::Full(p2, _, _, _)) = (b.get(x, y), b.get(nx, ny)) {
+                            assert!(p1 == p2 || b.get_color(x, y) != b.get_color(nx, ny));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_king_colorings() {
+        let polyominoes = get_pentominoes().unwrap();
+        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+        let solutions = generate_solutions(15, 4, &all_polyominoes);
+
+        let mut rng = Rng::new(5);
+
+        for soln in solutions.iter().take(10) {
+            let mut b = soln.clone();
+            color_board_randomly(&mut b, Adjacency::King, &mut rng);
+
+            // Pieces that only touch at a corner differ too
+            for y in 0..b.get_height() {
+                for x in 0..b.get_width() {
+                    for (nx, ny) in [(x+1, y+1), (x-1, y+1)] {
+                        if let (IndexedBoardState::Full(p1, _, _, _), IndexedBoardState::Full(p2, _, _, _)) = (b.get(x, y), b.get(nx, ny)) {
+                            assert!(p1 == p2 || b.get_color(x, y) != b.get_color(nx, ny));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_sample() {
+        let polyominoes = get_pentominoes().unwrap();
+        let all_polyominoes = poly_utils::build_variations(&polyominoes, Restrictions::RectangularSymmetry);
+        let solutions = generate_solutions(15, 4, &all_polyominoes);
+
+        // No pentomino can have six colors, so every sample is rejected
+        // before the (slow) solving step
+        let piece_rule = PieceColorRule { min_colors: 6, max_same_color: None };
+
+        let options = SampleOptions { samples: Some(20), seed: 11, piece_rule, ..Default::default() };
+        let first = sample_pairs(&solutions, &all_polyominoes, &options);
+        let second = sample_pairs(&solutions, &all_polyominoes, &options);
+
+        assert_eq!(first.samples, 20);
+        assert_eq!(first.histogram.breaks_piece_rule, 20);
+        assert_eq!(first.histogram, second.histogram);
+
+        let sample = draw_sample(&solutions, 12345, &all_polyominoes, Adjacency::Edge, &piece_rule, None);
+        let again = draw_sample(&solutions, 12345, &all_polyominoes, Adjacency::Edge, &piece_rule, None);
+
+        assert_eq!((sample.base, sample.target, sample.result.orientation), (again.base, again.target, again.result.orientation));
+        assert_eq!(sample.result.target.piece_cells(), again.result.target.piece_cells());
+    }
+}
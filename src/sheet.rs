@@ -0,0 +1,231 @@
+use std::fmt;
+
+use polyomino::point::Point;
+use polyomino::polyomino::Polyomino;
+
+use crate::colorableboard::ColorableBoard;
+use crate::colorpoint::cell_text;
+use crate::colorpoint::color_name;
+use crate::colorpoint::ColorPoint;
+use crate::colorpoint::OutputMode;
+use crate::colorpolyomino::ColorPolyomino;
+
+// The pieces of a board taken apart, each in its canonical orientation
+// (see ColorPolyomino::canonical_orientation), for publishing a puzzle.
+//
+// Pieces are packed onto shelves: tallest first, left to right with a
+// space between them, starting a new shelf below when the next piece
+// won't fit in the width. That keeps the sheet compact without any
+// real searching.
+
+pub struct Sheet {
+    pub width: i16,
+    pub height: i16,
+    pub pieces: Vec<(i16, i16, ColorPolyomino)>     // Left, top and the piece
+}
+
+/// Wide enough for the widest piece, whatever width is asked for
+pub fn layout_pieces(board: &ColorableBoard, width: i16) -> Sheet {
+    let mut pieces: Vec<ColorPolyomino> = board.polyominoes.iter().map(|p| p.canonical_orientation()).collect();
+
+    pieces.sort_by_key(|p| (-piece_size(p).1, p.pentomino_name(), p.get_id()));
+
+    let width = pieces.iter().map(|p| piece_size(p).0).max().unwrap_or(0).max(width);
+
+    let mut placed = Vec::new();
+    let (mut left, mut top, mut shelf_height) = (0, 0, 0);
+
+    for piece in pieces {
+        let (piece_width, piece_height) = piece_size(&piece);
+
+        if left > 0 && left + piece_width > width {
+            top += shelf_height + 1;
+            left = 0;
+            shelf_height = 0;
+        }
+
+        placed.push((left, top, piece));
+        left += piece_width + 1;
+        shelf_height = shelf_height.max(piece_height);
+    }
+
+    let used_width = placed.iter().map(|(left, _top, p)| left + piece_size(p).0).max().unwrap_or(0);
+
+    Sheet { width: used_width, height: top + shelf_height, pieces: placed }
+}
+
+fn piece_size(piece: &ColorPolyomino) -> (i16, i16) {
+    (piece.iter().map(|pt| pt.x()).max().unwrap_or(-1) + 1, piece.iter().map(|pt| pt.y()).max().unwrap_or(-1) + 1)
+}
+
+impl Sheet {
+    /// The piece and point on this space of the sheet, if any
+    pub fn get(&self, x: i16, y: i16) -> Option<(usize, &ColorPoint)> {
+        self.pieces.iter().enumerate()
+            .find_map(|(idx, (left, top, piece))| piece.iter()
+                      .find(|pt| pt.x() + left == x && pt.y() + top == y)
+                      .map(|pt| (idx, pt)))
+    }
+
+    /// Plain text, a color per space and '.' between the pieces, like the
+    /// colors section of a puzzle file
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                text.push(self.get(x, y).map_or('.', |(_idx, pt)| pt.get_color()));
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Each space is a square cell_size units wide, with heavier lines
+    /// around the edges of each piece
+    pub fn to_svg(&self, cell_size: u32) -> String {
+        let size = cell_size as i32;
+        let margin = (size / 4).max(1);
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+                              self.width as i32 * size + 2 * margin, self.height as i32 * size + 2 * margin);
+
+        svg.push_str(&svg_spaces(self.width, self.height, |x, y| self.get(x, y).map(|(idx, pt)| (idx, pt.get_color())),
+                                 cell_size, (margin, margin)));
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// SVG elements for a width x height grid of spaces, each given as the
+/// piece it belongs to and its color (None for a gap). The top left
+/// corner is at left, top
+pub fn svg_spaces<F: Fn(i16, i16) -> Option<(usize, char)>>(width: i16, height: i16, space: F, cell_size: u32, (left, top): (i32, i32)) -> String {
+    let size = cell_size as i32;
+    let mut svg = String::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if let Some((_idx, color)) = space(x, y) {
+                svg.push_str(&format!("  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"gray\" stroke-width=\"1\"/>\n",
+                                      left + x as i32 * size, top + y as i32 * size, size, size, color_name(color)));
+            }
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let idx = match space(x, y) {
+                Some((idx, _color)) => idx,
+                None => continue
+            };
+
+            let (px, py) = (left + x as i32 * size, top + y as i32 * size);
+
+            for ((nx, ny), (x1, y1, x2, y2)) in [((x-1, y), (px, py, px, py + size)),
+                                                 ((x+1, y), (px + size, py, px + size, py + size)),
+                                                 ((x, y-1), (px, py, px + size, py)),
+                                                 ((x, y+1), (px, py + size, px + size, py + size))] {
+                let neighbor = if nx < 0 || ny < 0 || nx >= width || ny >= height { None } else { space(nx, ny) };
+
+                if neighbor.map(|(other, _color)| other) != Some(idx) {
+                    svg.push_str(&format!("  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"3\" stroke-linecap=\"square\"/>\n",
+                                          x1, y1, x2, y2));
+                }
+            }
+        }
+    }
+
+    svg
+}
+
+/// For the terminal, colored squares like the board (or plain ASCII, see
+/// OutputMode)
+pub struct SheetView<'a> {
+    pub sheet: &'a Sheet,
+    pub mode: OutputMode
+}
+
+impl Sheet {
+    pub fn view(&self, mode: OutputMode) -> SheetView<'_> {
+        SheetView { sheet: self, mode }
+    }
+}
+
+impl fmt::Display for Sheet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.view(OutputMode::Color).fmt(f)
+    }
+}
+
+impl fmt::Display for SheetView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sheet = self.sheet;
+
+        for y in 0..sheet.height {
+            let row: Vec<String> = (0..sheet.width)
+                .map(|x| sheet.get(x, y).map_or(" ".to_string(), |(idx, pt)| cell_text(pt.get_color(), sheet.pieces[idx].2.pentomino_name(), self.mode)))
+                .collect();
+
+            writeln!(f, "{}", row.join(" ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colorableboard::ColorableBoard;
+    use crate::sheet::*;
+
+    // +-+-+-+-+-+-+
+    // |0 0 0 0 0|2|
+    // +-+-+-+-+-+ +
+    // |1 1 1 1|2 2|
+    // + +-+-+-+ + +
+    // |1|3 3 3|2 2|
+    // +-+-+-+-+-+-+
+    fn build_board() -> ColorableBoard {
+        let ids = vec![0, 0, 0, 0, 0, 2,
+                       1, 1, 1, 1, 2, 2,
+                       1, 3, 3, 3, 2, 2];
+
+        ColorableBoard::from_cells(6, 3, &ids.iter().map(|id| Some((*id, std::char::from_digit(*id as u32, 10).unwrap()))).collect())
+    }
+
+    #[test]
+    fn layout() {
+        let sheet = layout_pieces(&build_board(), 6);
+
+        // The I, the L and the three in a row fit on the first shelf,
+        // and the P goes on a second one
+        assert_eq!(sheet.pieces.len(), 4);
+        assert_eq!((sheet.width, sheet.height), (6, 9));
+        assert_eq!(sheet.get(0, 0).map(|(_idx, pt)| pt.get_color()), Some('0'));
+        assert!(sheet.get(1, 0).is_none());
+
+        let text = sheet.to_text();
+        assert_eq!(text.lines().count(), 9);
+        assert_eq!(text.chars().filter(|c| *c == '1').count(), 5);
+        assert_eq!(text.chars().filter(|c| *c == '3').count(), 3);
+    }
+
+    #[test]
+    fn views() {
+        let sheet = layout_pieces(&build_board(), 20);
+
+        assert_eq!(sheet.view(OutputMode::Plain).to_string().lines().next(), Some("0   1     3   2 2"));
+        assert_eq!(sheet.to_string(), sheet.view(OutputMode::Color).to_string());
+    }
+
+    #[test]
+    fn svg() {
+        let svg = layout_pieces(&build_board(), 20).to_svg(10);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 18);
+        assert_eq!(svg.matches("fill=\"red\"").count(), 5);
+    }
+}
@@ -0,0 +1,129 @@
+use std::env;
+
+use crate::colorableboard::BoardView;
+
+// Several boards printed next to each other, each with a caption above
+// it, so a base, its target and the answers can be compared at a glance.
+// Boards go left to right until the next one won't fit in the width,
+// then start a new row below. A board wider than the whole width gets a
+// row to itself.
+
+const GAP: usize = 3;
+
+pub fn side_by_side(boards: &[(String, BoardView)], width: usize) -> String {
+    // Each board as a caption and then its lines
+    let blocks: Vec<Vec<String>> = boards.iter()
+        .map(|(caption, view)| std::iter::once(caption.clone()).chain(view.to_string().lines().map(|l| l.to_string())).collect())
+        .collect();
+
+    let mut rows: Vec<Vec<&Vec<String>>> = Vec::new();
+    let mut used = 0;
+
+    for block in &blocks {
+        let block_width = block_width(block);
+
+        match rows.last_mut() {
+            Some(row) if used + GAP + block_width <= width => {
+                row.push(block);
+                used += GAP + block_width;
+            }
+            _ => {
+                rows.push(vec![block]);
+                used = block_width;
+            }
+        }
+    }
+
+    let mut out = String::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+
+        let height = row.iter().map(|block| block.len()).max().unwrap_or(0);
+
+        for line in 0..height {
+            let mut text = String::new();
+
+            for (j, block) in row.iter().enumerate() {
+                let cell = block.get(line).map_or("", |l| l.as_str());
+
+                if j + 1 < row.len() {
+                    text.push_str(cell);
+                    text.push_str(&" ".repeat(block_width(block) - visible_width(cell) + GAP));
+                } else {
+                    text.push_str(cell);
+                }
+            }
+
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// From $COLUMNS if the shell exports it, otherwise the usual 80
+pub fn terminal_width() -> usize {
+    env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).filter(|c| *c > 0).unwrap_or(80)
+}
+
+fn block_width(block: &Vec<String>) -> usize {
+    block.iter().map(|l| visible_width(l)).max().unwrap_or(0)
+}
+
+// Characters that take up room on the screen, skipping the ANSI escapes
+// that color them
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+
+    for c in s.chars() {
+        match (in_escape, c) {
+            (false, '\u{1b}') => in_escape = true,
+            (false, _) => width += 1,
+            (true, 'm') => in_escape = false,
+            (true, _) => ()
+        }
+    }
+
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colorableboard::Borders;
+    use crate::colorableboard::ColorableBoard;
+    use crate::colorpoint::OutputMode;
+    use crate::sidebyside::*;
+
+    fn build_board() -> ColorableBoard {
+        ColorableBoard::from_cells(2, 1, &vec![Some((0, '0')), Some((1, '0'))])
+    }
+
+    #[test]
+    fn fits() {
+        let b = build_board();
+        let out = side_by_side(&[("Base".to_string(), b.view(OutputMode::Plain)), ("Target".to_string(), b.view(OutputMode::Plain))], 80);
+
+        assert_eq!(out, "Base    Target\n+-+-+   +-+-+\n|0|0|   |0|0|\n+-+-+   +-+-+\n");
+    }
+
+    #[test]
+    fn wraps() {
+        let b = build_board();
+        let mut colors = b.view(OutputMode::Plain);
+        colors.borders = Borders::Colors;
+
+        let out = side_by_side(&[("Pieces".to_string(), b.view(OutputMode::Plain)), ("Colors".to_string(), colors)], 10);
+
+        assert_eq!(out, "Pieces\n+-+-+\n|0|0|\n+-+-+\n\nColors\n+-+-+\n|0 0|\n+-+-+\n");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(visible_width("\u{1b}[31m\u{25A0}\u{1b}[0m |"), 3);
+    }
+}
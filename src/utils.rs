@@ -1,13 +1,20 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
 
+use polyomino::board::Board;
 use polyomino::point::Point;
 use polyomino::point::SimplePoint;
 use polyomino::polyomino::Polyomino;
+use polyomino::solver::Solver;
+use polyomino::utils as poly_utils;
+use polyomino::utils::Restrictions;
 
 use crate::colorableboard::ColorableBoard;
+use crate::colorpolyomino::ColorPolyomino;
 
 #[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Orientation {
     Normal,
     OneEighty,
@@ -15,76 +22,323 @@ pub enum Orientation {
     FlipOneEighty
 }
 
+#[allow(dead_code)]
+impl Orientation {
+    pub fn all() -> Vec<Orientation> {
+        vec![Orientation::Normal, Orientation::OneEighty, Orientation::FlipHorizontally, Orientation::FlipOneEighty]
+    }
+
+    /// Doing one orientation after the other. These four make a group
+    /// in which everything is its own inverse and any two of the
+    /// non-Normal orientations make the third
+    pub fn then(&self, other: Orientation) -> Orientation {
+        match (*self, other) {
+            (Orientation::Normal, o) | (o, Orientation::Normal) => o,
+            (a, b) if a == b => Orientation::Normal,
+            (a, b) => Orientation::all().into_iter().find(|o| *o != Orientation::Normal && *o != a && *o != b).unwrap()
+        }
+    }
+
+    /// Where the point x,y ends up on a width x height board
+    pub fn apply(&self, x: i16, y: i16, width: i16, height: i16) -> (i16, i16) {
+        match self {
+            Orientation::Normal => (x, y),
+            Orientation::OneEighty => (width-x-1, height-y-1),
+            Orientation::FlipHorizontally => (width-x-1, y),
+            Orientation::FlipOneEighty => (x, height-y-1)
+        }
+    }
+}
+
 pub fn overlay(colored_board: &ColorableBoard, blank_board: &mut ColorableBoard, orientation: Orientation) {
-    match orientation {
-        Orientation::Normal => {
-            for x in 0..colored_board.width {
-                for y in 0..colored_board.height {
-                    if let Some(color) = colored_board.get_color(x, y) {
-                        blank_board.set_color(x, y, color);
-                    }
-                }
+    for x in 0..colored_board.width {
+        for y in 0..colored_board.height {
+            if let Some(color) = colored_board.get_color(x, y) {
+                let (ox, oy) = orientation.apply(x, y, colored_board.width, colored_board.height);
+                blank_board.set_color(ox, oy, color);
             }
         }
-        Orientation::OneEighty => {
-            for x in 0..colored_board.width {
-                for y in 0..colored_board.height {
-                    if let Some(color) = colored_board.get_color(x, y) {
-                        blank_board.set_color(colored_board.width-x-1, colored_board.height-y-1, color);
-                    }
-                }
-            }
+    }
+}
+
+/// How the colors on each piece have to be spread out. Pieces that are
+/// mostly one color are easy to place by eye, so tightening these makes
+/// for harder puzzles. The default only rules out mono-colored pieces
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PieceColorRule {
+    pub min_colors: usize,              // Fewest different colors on a piece
+    pub max_same_color: Option<usize>   // Most spaces of any one color on a piece
+}
+
+impl Default for PieceColorRule {
+    fn default() -> PieceColorRule {
+        PieceColorRule { min_colors: 2, max_same_color: None }
+    }
+}
+
+impl PieceColorRule {
+    pub fn allows(&self, poly: &ColorPolyomino) -> bool {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+
+        for pt in poly.iter() {
+            *counts.entry(pt.get_color()).or_insert(0) += 1;
         }
-        Orientation::FlipHorizontally => {
-            for x in 0..colored_board.width {
-                for y in 0..colored_board.height {
-                    if let Some(color) = colored_board.get_color(x, y) {
-                        blank_board.set_color(colored_board.width-x-1, y, color);
-                    }
-                }
-            }
+
+        counts.len() >= self.min_colors &&
+            self.max_same_color.is_none_or(|max| counts.values().all(|c| *c <= max))
+    }
+
+    pub fn allows_all(&self, board: &ColorableBoard) -> bool {
+        board.polyominoes.iter().all(|poly| self.allows(poly))
+    }
+}
+
+impl fmt::Display for PieceColorRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at least {} color(s) per piece", self.min_colors)?;
+        if let Some(max) = self.max_same_color {
+            write!(f, ", at most {} space(s) of one color", max)?;
         }
-        Orientation::FlipOneEighty => {
-            for x in 0..colored_board.width {
-                for y in 0..colored_board.height {
-                    if let Some(color) = colored_board.get_color(x, y) {
-                        blank_board.set_color(x, colored_board.height-y-1, color);
-                    }
-                }
+        Ok(())
+    }
+}
+
+/// What counts as a well balanced coloring of a whole board. Every
+/// kind needs at least two colors
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Balance {
+    #[default]
+    Exact,          // Every color covers the same number of spaces
+    Within(usize),  // Every color is within this many spaces of an even share
+    EqualPieces     // Every color is on the same number of pieces
+}
+
+impl Balance {
+    pub fn is_balanced(&self, board: &ColorableBoard) -> bool {
+        let counts = match self {
+            Balance::EqualPieces => piece_color_count(board),
+            _ => color_count(board)
+        };
+
+        if counts.len() < 2 {
+            return false;
+        }
+
+        match self {
+            Balance::Within(tolerance) => {
+                let area: i32 = counts.iter().sum();
+                counts.iter().all(|c| ((*c * counts.len() as i32 - area).unsigned_abs() as usize) <= tolerance * counts.len())
             }
+            _ => counts.iter().all(|c| *c == counts[0])
+        }
+    }
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Balance::Exact => write!(f, "the same number of spaces of every color"),
+            Balance::Within(tolerance) => write!(f, "every color within {} space(s) of an even share", tolerance),
+            Balance::EqualPieces => write!(f, "the same number of pieces of every color")
         }
     }
 }
 
-// Has a polyomino on the board that is colored just one color (these
-// are boring and we should ignore boards that have them)
-pub fn has_single_color_polyomino(board: &ColorableBoard) -> bool {
-    for poly in &board.polyominoes {
-        if poly.iter().all(|pt| pt.get_color() == poly[0].get_color()) {
+/// Two pieces that are the same shape with the same colors, however
+/// they are turned. Nobody could tell which of the two went where
+pub fn identical_pieces(a: &ColorPolyomino, b: &ColorPolyomino) -> bool {
+    let mut turned = a.clone();
+
+    for _ in 0..4 {
+        if turned.iter().eq(b.iter()) || turned.flip().iter().eq(b.iter()) {
             return true;
         }
+        turned = turned.rotate();
     }
 
     false
 }
 
+/// The ids of the first two identical pieces on the board, if any
+pub fn find_identical_pieces(board: &ColorableBoard) -> Option<(usize, usize)> {
+    for (i, a) in board.polyominoes.iter().enumerate() {
+        for b in &board.polyominoes[i+1..] {
+            if identical_pieces(a, b) {
+                return Some((a.get_id(), b.get_id()));
+            }
+        }
+    }
+
+    None
+}
 
 pub fn has_all_polyomino_patterns<T: Polyomino>(board: &ColorableBoard, all_polyominoes: &Vec<Vec<T>>) -> bool {
     all_polyominoes.iter().all(|p_rotations| p_rotations.iter().any(|p| has_single_color_polyomino_pattern(board, p)))
 }
 
-// Attempts to find a set of points in the board in the pattern of the polyomino that
-// are all the same color
+/// How many of the polyominoes have their pattern somewhere in the
+/// colors of the board. A board with all of them is a solution
+pub fn count_polyomino_patterns<T: Polyomino>(board: &ColorableBoard, all_polyominoes: &Vec<Vec<T>>) -> usize {
+    all_polyominoes.iter().filter(|p_rotations| p_rotations.iter().any(|p| has_single_color_polyomino_pattern(board, p))).count()
+}
+
+/// Every way of tiling a board of the same size using the (colored)
+/// polyominoes of this board, in the order the solver finds them
+pub fn find_colored_tilings(board: &ColorableBoard) -> Vec<ColorableBoard> {
+    let colored_polys = poly_utils::build_variations(&board.polyominoes, Restrictions::RectangularSymmetry);
+
+    let mut empty_board = Board::new(board.get_width(), board.get_height());
+    let mut solver = Solver::new(&mut empty_board, &colored_polys);
+
+    solver.solve().iter().map(|s| ColorableBoard::new(s)).collect()
+}
+
+/// Like find_colored_tilings, but stops looking once it has found limit
+/// tilings. The solver always lists every tiling, which can take far
+/// longer than a budget allows, so this does its own search: fill the
+/// first empty space with each unused piece in each orientation, and
+/// backtrack. Spaces are filled across the short side of the board
+/// first (column by column on a wide board), which leaves the fewest
+/// places open. The tilings always come in the same order, so a smaller
+/// limit gets the first few of a larger one
+pub fn find_colored_tilings_up_to(board: &ColorableBoard, limit: usize) -> Vec<ColorableBoard> {
+    let colored_polys = poly_utils::build_variations(&board.polyominoes, Restrictions::RectangularSymmetry);
+
+    let (width, height) = (board.get_width(), board.get_height());
+    let columns = width > height;
+    let order = if columns {
+        (0..width).flat_map(|x| (0..height).map(move |y| (x, y))).collect()
+    } else {
+        (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).collect()
+    };
+
+    // The first point of a piece has to go in the first empty space
+    let shapes = colored_polys.iter().map(|variations| variations.iter().map(|p| {
+        let points: Vec<(i16, i16)> = p.iter().map(|pt| (pt.x(), pt.y())).collect();
+        let (ax, ay) = points.iter().copied().min_by_key(|(x, y)| if columns { (*x, *y) } else { (*y, *x) }).unwrap_or_default();
+        ((ax, ay), points.iter().map(|(x, y)| (x - ax, y - ay)).collect())
+    }).collect()).collect();
+
+    let mut search = TilingSearch {
+        width,
+        height,
+        order,
+        polys: &colored_polys,
+        shapes,
+        filled: vec![false; (width as usize) * (height as usize)],
+        used: vec![false; colored_polys.len()],
+        placed: Vec::new(),
+        tilings: Vec::new(),
+        limit
+    };
+
+    search.extend();
+    search.tilings
+}
+
+// An orientation of a piece as its first point (in the order spaces are
+// filled) and its spaces relative to that point
+type Shape = ((i16, i16), Vec<(i16, i16)>);
+
+struct TilingSearch<'a> {
+    width: i16,
+    height: i16,
+    order: Vec<(i16, i16)>,                         // The spaces in the order they're filled
+    polys: &'a Vec<Vec<ColorPolyomino>>,
+    shapes: Vec<Vec<Shape>>,                        // Each orientation of each piece
+    filled: Vec<bool>,
+    used: Vec<bool>,
+    placed: Vec<(usize, usize, i16, i16)>,          // Piece, variation and where it went
+    tilings: Vec<ColorableBoard>,
+    limit: usize
+}
+
+impl TilingSearch<'_> {
+    fn extend(&mut self) {
+        if self.tilings.len() >= self.limit {
+            return;
+        }
+
+        let (x, y) = match self.order.iter().find(|(x, y)| !self.filled[(x + y * self.width) as usize]) {
+            Some(space) => *space,
+            None => {
+                if self.used.iter().all(|u| *u) {
+                    self.record();
+                }
+                return;
+            }
+        };
+
+        for piece in 0..self.shapes.len() {
+            if self.used[piece] {
+                continue;
+            }
+
+            for variation in 0..self.shapes[piece].len() {
+                if !self.fits(piece, variation, x, y) {
+                    continue;
+                }
+
+                let (ax, ay) = self.shapes[piece][variation].0;
+
+                self.set(piece, variation, x, y, true);
+                self.used[piece] = true;
+                self.placed.push((piece, variation, x - ax, y - ay));
+
+                self.extend();
+
+                self.placed.pop();
+                self.used[piece] = false;
+                self.set(piece, variation, x, y, false);
+
+                if self.tilings.len() >= self.limit {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn fits(&self, piece: usize, variation: usize, x: i16, y: i16) -> bool {
+        self.shapes[piece][variation].1.iter().all(|(dx, dy)| {
+            let (cx, cy) = (x + dx, y + dy);
+            cx >= 0 && cy >= 0 && cx < self.width && cy < self.height && !self.filled[(cx + cy * self.width) as usize]
+        })
+    }
+
+    fn set(&mut self, piece: usize, variation: usize, x: i16, y: i16, filled: bool) {
+        for (dx, dy) in &self.shapes[piece][variation].1 {
+            self.filled[(x + dx + (y + dy) * self.width) as usize] = filled;
+        }
+    }
+
+    fn record(&mut self) {
+        let mut b = Board::new(self.width, self.height);
+
+        for (piece, variation, x, y) in &self.placed {
+            b.add_polyomino(&self.polys[*piece][*variation], &SimplePoint::new(*x, *y));
+        }
+
+        self.tilings.push(ColorableBoard::new(&b));
+    }
+}
+
+/// Attempts to find a set of points in the board in the pattern of the polyomino that
+/// are all the same color
 pub fn has_single_color_polyomino_pattern<T: Polyomino>(board: &ColorableBoard, polyomino: &T) -> bool {
+    find_single_color_polyomino_pattern(board, polyomino).is_some()
+}
+
+/// Like has_single_color_polyomino_pattern, but returns the points that make up the pattern
+pub fn find_single_color_polyomino_pattern<T: Polyomino>(board: &ColorableBoard, polyomino: &T) -> Option<Vec<SimplePoint>> {
     for x in 0..board.get_width() {
         for y in 0..board.get_height() {
             if has_single_color_polyomino_pattern_at(board, polyomino, x, y) {
-                return true;
+                return Some(polyomino.iter().map(|pt| SimplePoint::new(x+pt.x(), y+pt.y())).collect());
             }
         }
     }
 
-    false
+    None
 }
 
 // Check for two things
@@ -165,6 +419,18 @@ pub fn color_count(solution: &ColorableBoard) -> Vec<i32> {
     used_colors.values().cloned().collect()
 }
 
+/// How many pieces are each color, going by the first space of each
+/// piece. Only makes sense for boards with one color per piece
+pub fn piece_color_count(solution: &ColorableBoard) -> Vec<i32> {
+    let mut used_colors: HashMap<char, i32> = HashMap::new();
+
+    for poly in &solution.polyominoes {
+        *used_colors.entry(poly[0].get_color()).or_insert(0) += 1;
+    }
+
+    used_colors.values().cloned().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use polyomino::board::Board;
@@ -358,6 +624,76 @@ mod tests {
         assert!(!has_single_color_polyomino_pattern(&b, &piece));
     }
 
+    #[test]
+    fn piece_color_rules() {
+        let b = build_board();
+
+        assert!(PieceColorRule::default().allows_all(&b));
+        assert!(!PieceColorRule { min_colors: 3, max_same_color: None }.allows_all(&b));
+        assert!(PieceColorRule { min_colors: 2, max_same_color: Some(4) }.allows_all(&b));
+        assert!(!PieceColorRule { min_colors: 2, max_same_color: Some(3) }.allows_all(&b));
+    }
+
+    #[test]
+    fn orientations_compose() {
+        for a in Orientation::all() {
+            for b in Orientation::all() {
+                let (x, y) = a.apply(1, 2, 5, 4);
+                assert_eq!(b.apply(x, y, 5, 4), a.then(b).apply(1, 2, 5, 4));
+            }
+        }
+    }
+
+    #[test]
+    fn balance() {
+        // 10 spaces of '0' and 15 of '1'
+        let b = build_board();
+
+        assert!(!Balance::Exact.is_balanced(&b));
+        assert!(!Balance::Within(2).is_balanced(&b));
+        assert!(Balance::Within(3).is_balanced(&b));
+    }
+
+    #[test]
+    fn identical() {
+        let b = build_board();
+        let piece = &b.polyominoes[0];
+
+        assert!(identical_pieces(piece, &piece.rotate().flip()));
+        assert!(!identical_pieces(piece, &b.polyominoes[1]));
+
+        let mut recolored = piece.clone();
+        recolored[0].set_color('9');
+        assert!(!identical_pieces(piece, &recolored));
+
+        assert_eq!(find_identical_pieces(&b), None);
+    }
+
+    #[test]
+    fn bounded_tilings() {
+        // +-+-+-+
+        // |0 0|1|
+        // +-+-+ +
+        // |2 2|1|
+        // +-+-+-+
+        let b = ColorableBoard::from_cells(3, 2, &vec![Some((0, '0')), Some((0, '1')), Some((1, '0')),
+                                                       Some((2, '2')), Some((2, '3')), Some((1, '4'))]);
+
+        let all = find_colored_tilings(&b);
+        assert!(all.len() > 1);
+
+        assert_eq!(find_colored_tilings_up_to(&b, 1).len(), 1);
+        assert_eq!(find_colored_tilings_up_to(&b, 1000).len(), all.len());
+        assert!(find_colored_tilings_up_to(&b, 1000).iter().all(|t| (0..3).all(|x| (0..2).all(|y| t.get_color(x, y).is_some()))));
+    }
+
+    #[test]
+    fn count_patterns() {
+        let b = build_board();
+
+        assert_eq!(count_polyomino_patterns(&b, &vec![vec![build_x()], vec![build_l()], vec![build_flat_l()]]), 2);
+    }
+
     #[test]
     fn has_all_variations_of_patterns() {
         if let Ok(mut polyominoes) = poly_utils::get_polyominoes::<ColorPolyomino>(PredefinedPolyominoes::Pentominoes) {
@@ -377,9 +713,9 @@ mod tests {
             color_board(&mut soln10);
             overlay(&soln10, &mut soln58, Orientation::Normal);
 
-            assert!(has_single_color_polyomino(&soln10));
+            assert!(!PieceColorRule::default().allows_all(&soln10));
             
-            assert!(!has_single_color_polyomino(&soln58));
+            assert!(PieceColorRule::default().allows_all(&soln58));
 
             // This one has the right color pattern because all the polyominoes are mono-colored
             println!("Solution #10\n{}", soln10);